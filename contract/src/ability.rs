@@ -0,0 +1,125 @@
+use crate::*;
+
+const BASE_ENERGY: u16 = 50;
+const ENERGY_PER_STAMINA: u16 = 10;
+const ENERGY_PER_ROUND: u16 = 10;
+const HEAL_PERCENTAGE: u32 = 25;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(crate = "near_sdk::serde", rename_all = "snake_case")]
+pub enum Ability {
+    /// Doubles the damage of the warrior attacks in the round
+    PowerStrike,
+    /// Restores a quarter of the max health before the damage of the round
+    Heal,
+    /// Stuns the opponent for the next round
+    Stun,
+}
+
+impl Ability {
+    pub fn energy_cost(&self) -> u16 {
+        match self {
+            Ability::PowerStrike => 30,
+            Ability::Heal => 40,
+            Ability::Stun => 60,
+        }
+    }
+
+    /// Rounds before the ability can be used again
+    pub fn cooldown(&self) -> u16 {
+        match self {
+            Ability::PowerStrike => 2,
+            Ability::Heal => 4,
+            Ability::Stun => 5,
+        }
+    }
+
+    /// Returns the damage done and taken by the warrior using the ability
+    pub(crate) fn apply_to_damage(&self, damage_done: u16, damage_taken: u16) -> (u16, u16) {
+        match self {
+            Ability::PowerStrike => (damage_done.saturating_mul(2), damage_taken),
+            Ability::Heal | Ability::Stun => (damage_done, damage_taken),
+        }
+    }
+
+    pub(crate) fn get_opponent_effect(&self) -> Option<StatusEffect> {
+        match self {
+            Ability::Stun => Some(StatusEffect { kind: StatusEffectKind::Stun, rounds: 1, strength: 0 }),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn get_heal(&self, max_health: u16) -> u16 {
+        match self {
+            Ability::Heal => (max_health as u32 * HEAL_PERCENTAGE / 100) as u16,
+            _ => 0,
+        }
+    }
+}
+
+/// Energy and cooldowns of a warrior in the battle
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AbilityState {
+    pub energy: u16,
+    pub max_energy: u16,
+    /// Abilities on cooldown with the round from which they can be used again
+    pub ready_at: Vec<(Ability, u16)>,
+}
+
+impl AbilityState {
+    pub fn new(warrior: &Warrior) -> AbilityState {
+        let max_energy = BASE_ENERGY.saturating_add(warrior.stamina.saturating_mul(ENERGY_PER_STAMINA));
+        AbilityState { energy: max_energy, max_energy, ready_at: vec![] }
+    }
+
+    pub fn check(&self, warrior: &Warrior, ability: Ability, round: u16) -> Result<(), String> {
+        if !warrior.abilities.contains(&ability) {
+            return Err(format!("Ability {:?} is not available", ability));
+        }
+        if let Some((_, ready_at)) = self.ready_at.iter().find(|(used_ability, _)| *used_ability == ability) {
+            if round < *ready_at {
+                return Err(format!("Ability {:?} is on cooldown until round {}", ability, ready_at));
+            }
+        }
+        if self.energy < ability.energy_cost() {
+            return Err(format!("Not enough energy for {:?}, {} is required", ability, ability.energy_cost()));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn spend(&mut self, ability: Ability, round: u16) {
+        self.energy -= ability.energy_cost();
+        self.ready_at.retain(|(used_ability, _)| *used_ability != ability);
+        self.ready_at.push((ability, round + ability.cooldown()));
+    }
+
+    pub(crate) fn regenerate(&mut self) {
+        self.energy = std::cmp::min(self.energy.saturating_add(ENERGY_PER_ROUND), self.max_energy);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ability_state() {
+        let mut warrior = Warrior::new(1, None);
+        let mut ability_state = AbilityState::new(&warrior);
+        assert_eq!(ability_state.energy, BASE_ENERGY + BASE_STAMINA * ENERGY_PER_STAMINA);
+
+        assert!(ability_state.check(&warrior, Ability::Heal, 0).is_err());
+        warrior.abilities.push(Ability::Heal);
+        assert_eq!(ability_state.check(&warrior, Ability::Heal, 0), Ok(()));
+
+        ability_state.spend(Ability::Heal, 0);
+        assert!(ability_state.check(&warrior, Ability::Heal, 3).unwrap_err().contains("cooldown"));
+        assert!(ability_state.check(&warrior, Ability::PowerStrike, 0).is_err());
+
+        ability_state.regenerate();
+        ability_state.regenerate();
+        assert_eq!(ability_state.check(&warrior, Ability::PowerStrike, 0), Ok(()));
+        assert_eq!(ability_state.check(&warrior, Ability::Heal, 4), Ok(()));
+    }
+}
@@ -0,0 +1,115 @@
+use crate::*;
+
+/// Finished battles are kept in full for a week by default
+pub(crate) const DEFAULT_BATTLE_ARCHIVE_AGE: Timestamp = 7 * 86_400_000_000_000;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BattleSummary {
+    pub warrior_1_account_id: Option<AccountId>,
+    pub warrior_2_account_id: Option<AccountId>,
+    pub winner: Option<u32>,
+    pub reward: Balance,
+    pub finished_at: Timestamp,
+}
+
+impl From<&BattleToSave> for BattleSummary {
+    fn from(battle: &BattleToSave) -> Self {
+        BattleSummary {
+            warrior_1_account_id: battle.warrior_1.account_id.clone(),
+            warrior_2_account_id: battle.warrior_2.account_id.clone(),
+            winner: battle.winner,
+            reward: battle.reward,
+            finished_at: battle.last_action_timestamp,
+        }
+    }
+}
+
+#[near_bindgen]
+impl DeFight {
+    pub fn set_battle_archive_age(&mut self, battle_archive_age: Timestamp) {
+        self.assert_owner();
+        self.battle_archive_age = battle_archive_age;
+    }
+
+    pub fn get_battle_archive_age(&self) -> Timestamp {
+        self.battle_archive_age
+    }
+
+    /// Compacts finished battles older than the archive age into summaries. The full battle is logged
+    /// with the "archive_battle" event and the freed storage is returned to the registered player,
+    /// except for legacy battles which were never charged in full.
+    /// Anyone can call it, unfinished and recent battles are skipped. Returns ids of the archived battles.
+    pub fn archive_battles(&mut self, battle_ids: Vec<BattleId>) -> Vec<BattleId> {
+        let mut archived_battle_ids = vec![];
+
+        for battle_id in battle_ids {
+            let battle = match self.get_saved_battle(&battle_id) {
+                Some(battle) => battle,
+                None => continue,
+            };
+
+            if battle.winner.is_none()
+                || env::block_timestamp() < battle.last_action_timestamp.saturating_add(self.battle_archive_age) {
+                continue;
+            }
+
+            let is_charged = !self.is_legacy_battle(&battle_id);
+            let initial_storage_usage = env::storage_usage();
+            self.remove_saved_battle(&battle_id);
+            self.battle_summaries.insert(&battle_id, &BattleSummary::from(&battle));
+
+            // Only storage the player has paid for is released
+            if let Some(account_id) = battle.warrior_1.account_id.as_ref().filter(|_| is_charged) {
+                if self.storage_accounts.get(account_id).is_some() {
+                    self.charge_storage(account_id, initial_storage_usage);
+                }
+            }
+
+            emit_event("archive_battle", BattleArchiveEventData { battle_id, battle });
+            archived_battle_ids.push(battle_id);
+        }
+
+        archived_battle_ids
+    }
+
+    pub fn get_battle_summary(&self, battle_id: BattleId) -> Option<BattleSummary> {
+        self.get_saved_battle(&battle_id)
+            .map(|battle| BattleSummary::from(&battle))
+            .or_else(|| self.battle_summaries.get(&battle_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::setup_contract;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::{testing_env, MockedBlockchain};
+
+    #[test]
+    fn test_archive_battles() {
+        let mut contract = setup_contract();
+        let account_id: AccountId = accounts(1).into();
+
+        let mut finished_battle = BattleToSave::new(
+            Warrior::new(1, Some(account_id.clone())), &Profile::default(), Warrior::new(2, None), None, BattleFormat::Elimination,
+        );
+        finished_battle.winner = Some(1);
+        let initial_storage_usage = env::storage_usage();
+        contract.save_battle(&0, &finished_battle);
+        contract.save_battle(&1, &BattleToSave::new(Warrior::new(1, None), &Profile::default(), Warrior::new(2, None), None, BattleFormat::Elimination));
+        contract.charge_storage(&account_id, initial_storage_usage);
+        let used_bytes = contract.storage_accounts.get(&account_id).unwrap().used_bytes;
+
+        assert!(contract.archive_battles(vec![0, 1]).is_empty());
+
+        testing_env!(VMContextBuilder::new().block_timestamp(DEFAULT_BATTLE_ARCHIVE_AGE).build());
+        assert_eq!(contract.archive_battles(vec![0, 1, 2]), vec![0]);
+
+        assert!(contract.get_saved_battle(&0).is_none());
+        assert!(contract.get_saved_battle(&1).is_some());
+        assert_eq!(contract.get_battle_summary(0).unwrap().winner, Some(1));
+        assert!(contract.storage_accounts.get(&account_id).unwrap().used_bytes < used_bytes);
+    }
+}
@@ -1,11 +1,55 @@
 use crate::*;
-use lazy_static::lazy_static;
-use strum::{EnumVariantNames, VariantNames};
-use regex::Regex;
+use std::fmt;
 use std::str::FromStr;
 use near_sdk::env::random_seed;
+use crate::effects::{add_effect, tick_effects};
+use crate::migration::{BattleToSaveV0, BattleToSaveV1, BattleToSaveV2, BattleToSaveV3, WarriorV1, WarriorV2, WarriorV5};
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(crate = "near_sdk::serde", rename_all = "snake_case")]
+pub enum BattleFormat {
+    /// The battle is over when health of a warrior runs out
+    Elimination,
+    /// Duels until a warrior wins the majority of them, health is restored between duels
+    BestOf { duels: u8 },
+    /// Elimination limited by rounds, after the last one the higher remaining health percentage wins
+    Rounds { rounds: u16 },
+}
+
+impl Default for BattleFormat {
+    fn default() -> Self {
+        BattleFormat::Elimination
+    }
+}
+
+impl BattleFormat {
+    const MAX_DUELS: u8 = 9;
+    const MAX_ROUNDS: u16 = 100;
+
+    pub(crate) fn assert_valid(&self) {
+        match *self {
+            BattleFormat::Elimination => {},
+            BattleFormat::BestOf { duels } => assert!(
+                duels % 2 == 1 && duels <= BattleFormat::MAX_DUELS,
+                "Number of duels must be odd and not greater than {}", BattleFormat::MAX_DUELS
+            ),
+            BattleFormat::Rounds { rounds } => assert!(
+                rounds > 0 && rounds <= BattleFormat::MAX_ROUNDS,
+                "Number of rounds must be from 1 to {}", BattleFormat::MAX_ROUNDS
+            ),
+        }
+    }
+}
+
+/// Options of the battle chosen in `start_battle`
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, Copy)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BattleSettings {
+    pub bot_difficulty: BotDifficulty,
+    pub format: BattleFormat,
+}
 
-#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug)]
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone)]
 #[serde(crate = "near_sdk::serde")]
 pub struct Battle {
     pub warrior_1: Warrior,
@@ -19,6 +63,20 @@ pub struct Battle {
     pub warrior_1_last_protect: Option<String>,
     pub warrior_2_last_attack: Option<String>,
     pub warrior_2_last_protect: Option<String>,
+    pub format: BattleFormat,
+    /// Rounds played in the battle
+    pub round: u16,
+    /// Duels finished in the best-of battle
+    pub duel: u8,
+    pub warrior_1_duel_wins: u8,
+    pub warrior_2_duel_wins: u8,
+    pub warrior_1_max_health: u16,
+    pub warrior_2_max_health: u16,
+    pub warrior_1_abilities: AbilityState,
+    pub warrior_2_abilities: AbilityState,
+    /// Active status effects of the warriors
+    pub warrior_1_effects: Vec<StatusEffect>,
+    pub warrior_2_effects: Vec<StatusEffect>,
 }
 
 #[derive(BorshSerialize, BorshDeserialize)]
@@ -40,7 +98,8 @@ impl From<EBattleConfig> for BattleConfig {
     }
 }
 
-#[derive(PartialEq, EnumVariantNames, Debug, Copy, Clone)]
+#[derive(Serialize, Deserialize, PartialEq, Debug, Copy, Clone)]
+#[serde(crate = "near_sdk::serde")]
 pub enum Part {
     Head,
     Neck,
@@ -64,6 +123,34 @@ impl FromStr for Part {
     }
 }
 impl Part {
+    pub const ALL: [Part; 5] = [Part::Head, Part::Neck, Part::Chest, Part::Groin, Part::Legs];
+
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+
+    /// Damage of a hit to the part in percent of the base damage
+    pub fn damage_multiplier(&self) -> u32 {
+        match self {
+            Part::Head => 150,
+            Part::Neck => 125,
+            Part::Chest => 100,
+            Part::Groin => 110,
+            Part::Legs => 75,
+        }
+    }
+
+    /// Chance in percent that a hit to the part does double damage
+    pub fn crit_chance(&self) -> u8 {
+        match self {
+            Part::Head => 20,
+            Part::Neck => 15,
+            Part::Chest => 5,
+            Part::Groin => 10,
+            Part::Legs => 5,
+        }
+    }
+
     fn as_str(&self) -> String {
         match self {
             Part::Head => "Head".to_string(),
@@ -75,19 +162,48 @@ impl Part {
     }
 }
 
-#[derive(PartialEq, Debug)]
-pub enum InputError {
-    WrongActions { actions : Vec<ParseError> },
-    TooFewActions,
+/// All errors of the move input, the parser doesn't stop at the first one
+#[derive(PartialEq, Debug, Clone)]
+pub struct InputError {
+    pub errors: Vec<ParseError>,
 }
 
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone)]
 pub enum ParseError {
-    WrongAction { action : String },
-    WrongPart { part : String },
+    WrongFormat { token: String },
+    WrongAction { action: String },
+    WrongPart { part: String },
+    MissingAttack,
+    MissingProtect,
+    TooManyAttacks { max: usize },
+    TooManyProtects { max: usize },
+    SameProtectedPart { part: Part },
 }
 
-#[derive(PartialEq, EnumVariantNames, Debug, Copy, Clone)]
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::WrongFormat { token } => write!(f, "'{}' must be in the Action:Part format", token),
+            ParseError::WrongAction { action } => write!(f, "Action '{}' doesn't exist in the game", action),
+            ParseError::WrongPart { part } => write!(f, "Part '{}' doesn't exist in the game", part),
+            ParseError::MissingAttack => write!(f, "Attack is not specified"),
+            ParseError::MissingProtect => write!(f, "Protect is not specified"),
+            ParseError::TooManyAttacks { max } => write!(f, "You can attack at most {} parts", max),
+            ParseError::TooManyProtects { max } => write!(f, "You can protect at most {} parts", max),
+            ParseError::SameProtectedPart { part } => write!(f, "Part {:?} is protected twice", part),
+        }
+    }
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let messages: Vec<String> = self.errors.iter().map(ParseError::to_string).collect();
+        write!(f, "{}", messages.join("; "))
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Copy, Clone)]
+#[serde(crate = "near_sdk::serde")]
 pub enum ActionType {
     Attack,
     Protect,
@@ -105,95 +221,181 @@ impl FromStr for ActionType {
     }
 }
 
-#[derive(PartialEq, Debug, Copy, Clone)]
-pub struct MoveData {
-    action: ActionType,
-    part: Part,
+/// Attacked and protected parts of a warrior in the round
+#[derive(PartialEq, Debug, Clone)]
+pub struct WarriorMove {
+    pub attacks: Vec<Part>,
+    pub protects: Vec<Part>,
+    pub ability: Option<Ability>,
+    pub consumable: Option<Consumable>,
 }
 
-impl MoveData {
-    pub fn new(action: ActionType, part: Part) -> MoveData {
-        MoveData { action, part }
-    }
+/// Move in the JSON form, e.g. `{"attack": "Head", "protect": "Legs"}`. Warriors with several attack
+/// or protect slots list the other parts in `extra_attacks` and `extra_protects`.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MoveInput {
+    pub attack: Part,
+    pub protect: Part,
+    #[serde(default)]
+    pub extra_attacks: Vec<Part>,
+    #[serde(default)]
+    pub extra_protects: Vec<Part>,
+    #[serde(default)]
+    pub ability: Option<Ability>,
+    #[serde(default)]
+    pub consumable: Option<Consumable>,
 }
 
+impl MoveInput {
+    pub fn to_warrior_move(&self, attack_slots: usize, protect_slots: usize) -> Result<WarriorMove, InputError> {
+        let attacks = std::iter::once(self.attack).chain(self.extra_attacks.iter().copied()).collect();
+        let protects = std::iter::once(self.protect).chain(self.extra_protects.iter().copied()).collect();
 
-lazy_static! {
-    // A Regular Expression used to find variant names in target strings. 
-    static ref ACTION_EXPR: Regex = {
-        // Piece together the expression from Thing's variant names.
-        let expr_str = ActionType::VARIANTS.join("|");
+        check_move(attacks, protects, attack_slots, protect_slots, vec![])
+            .map(|warrior_move| WarriorMove { ability: self.ability, consumable: self.consumable, ..warrior_move })
+    }
+}
 
-        Regex::new(&expr_str).unwrap()  
-    };
+/// Every attack does the part damage to an unprotected part and a quarter of it to a protected one.
+/// The attacker damage bonus is added to the part damage and a hit is doubled when its crit roll is under
+/// the part crit chance. The part armor reduces the damage by `armor / (100 + armor)` and the defender
+/// resistances reduce it by the attacker damage types.
+fn get_damage(
+    attacker: &Warrior,
+    defender: &Warrior,
+    attacker_move: &WarriorMove,
+    defender_move: &WarriorMove,
+    defender_missed_action: bool,
+    crit_rolls: &[u8],
+) -> u16 {
+    let damage: u32 = attacker_move.attacks.iter().zip(crit_rolls)
+        .map(|(part, crit_roll)| {
+            let mut damage = 3 * attacker.strength as u32 * part.damage_multiplier() / 200
+                * (100 + attacker.damage_bonus as u32) / 100;
+            if is_crit(part, *crit_roll) {
+                damage *= 2;
+            }
+            if !defender_missed_action && defender_move.protects.contains(part) {
+                damage /= 4;
+            }
+            damage * 100 / (100 + defender.armor[part.index()] as u32)
+        })
+        .sum::<u32>() * defender.get_damage_taken_percentage(attacker) / 100;
 
-    static ref PART_EXPR: Regex = {    
-        // Piece together the expression from Thing's variant names.
-        let expr_str = Part::VARIANTS.join("|");
-        
-        Regex::new(&expr_str).unwrap()  
-    };
+    std::cmp::min(damage, u16::MAX as u32) as u16
 }
 
-fn part_validator(string_to_validate: &str) -> Result<MoveData, ParseError> {
-	let result: Vec<&str> = string_to_validate.split(":").collect();
+fn is_crit(part: &Part, crit_roll: u8) -> bool {
+    crit_roll % 100 < part.crit_chance()
+}
 
-    if ACTION_EXPR.captures(result[0]).is_none() {
-        return Err(ParseError::WrongAction { action : result[0].to_string() });
+/// Crits make the defender bleed, hits to unprotected parts put the attacker equipment effects on it
+fn get_hit_effects(
+    attacker: &Warrior,
+    attacker_move: &WarriorMove,
+    defender_move: &WarriorMove,
+    defender_missed_action: bool,
+    crit_rolls: &[u8],
+) -> Vec<StatusEffect> {
+    let mut effects = vec![];
+
+    if attacker_move.attacks.iter().zip(crit_rolls).any(|(part, crit_roll)| is_crit(part, *crit_roll)) {
+        effects.push(StatusEffect::bleed(attacker));
     }
 
-    if PART_EXPR.captures(result[1]).is_none() {
-        return Err(ParseError::WrongPart { part : result[1].to_string() });
+    if attacker_move.attacks.iter().any(|part| defender_missed_action || !defender_move.protects.contains(part)) {
+        // Stuns are given only by the ability, see `StatusEffect::is_item_effect`
+        effects.extend(attacker.hit_effects.iter().filter(|effect| effect.is_item_effect()).cloned());
     }
 
-    let action = ActionType::from_str(result[0]).unwrap();
-    let part = Part::from_str(result[1]).unwrap();
-
-    Ok(MoveData::new(action, part))
+    effects
 }
 
-pub fn parse_move(params : &str) -> Result<Vec<MoveData>, InputError> {
-	let results : Vec<_> = params.split_whitespace()
-		.map(part_validator)
-		.collect();
+fn join_parts(parts: &[Part]) -> String {
+    parts.iter().map(Part::as_str).collect::<Vec<_>>().join(",")
+}
 
-	let (ok_iter, err_iter) : (Vec<_>, Vec<_>) = results.into_iter()
-		.map(
-			|result|
-				match result {
-					Ok(v) => (Some(v), None),
-					Err(e) => (None, Some(e))
-				})
-		.unzip();
+/// Parses an `Action:Part` token, both the action and the part errors are reported
+fn parse_action(token: &str, errors: &mut Vec<ParseError>) -> Option<(ActionType, Part)> {
+    let (action, part) = match token.split_once(':') {
+        Some(action_part) => action_part,
+        None => {
+            errors.push(ParseError::WrongFormat { token: token.to_string() });
+            return None;
+        }
+    };
 
-	let errors : Vec<_> = err_iter.into_iter()
-		.filter_map(|error| error)
-		.collect();
+    let action_type = ActionType::from_str(action);
+    if action_type.is_err() {
+        errors.push(ParseError::WrongAction { action: action.to_string() });
+    }
 
-	if !errors.is_empty() {
-		return Err(InputError::WrongActions { actions : errors });
-	}
+    let part_type = Part::from_str(part);
+    if part_type.is_err() {
+        errors.push(ParseError::WrongPart { part: part.to_string() });
+    }
 
-	let actions : Vec<_> = ok_iter.into_iter()
-		.filter_map(|position| position)
-		.collect();
+    Some((action_type.ok()?, part_type.ok()?))
+}
 
-	if actions.len() < 2 {
-		return Err(InputError::TooFewActions);
-	}
+/// Parses a move like `"Attack:Head Protect:Legs"`: `Action:Part` tokens separated by whitespace in any order.
+/// A move has from one to `attack_slots` attacks and from one to `protect_slots` different protected parts,
+/// so with a single slot of each it is exactly one Attack and one Protect.
+pub fn parse_move(params: &str, attack_slots: usize, protect_slots: usize) -> Result<WarriorMove, InputError> {
+    let mut errors = vec![];
+    let mut attacks = vec![];
+    let mut protects = vec![];
+
+    for token in params.split_whitespace() {
+        match parse_action(token, &mut errors) {
+            Some((ActionType::Attack, part)) => attacks.push(part),
+            Some((ActionType::Protect, part)) => protects.push(part),
+            None => {},
+        }
+    }
 
-	Ok(actions)
+    check_move(attacks, protects, attack_slots, protect_slots, errors)
 }
 
-#[derive(Debug)]
-pub struct Action {
-    warrior_1_attack: String,
-    warrior_1_protect: String,
-    warrior_2_attack: String,
-    warrior_2_protect: String,
+/// Checks the parts of a move against the warrior slots, `errors` are the errors found before
+fn check_move(
+    attacks: Vec<Part>,
+    protect_parts: Vec<Part>,
+    attack_slots: usize,
+    protect_slots: usize,
+    mut errors: Vec<ParseError>,
+) -> Result<WarriorMove, InputError> {
+    let mut protects = vec![];
+    for part in protect_parts {
+        if protects.contains(&part) {
+            errors.push(ParseError::SameProtectedPart { part });
+        } else {
+            protects.push(part);
+        }
+    }
+
+    if attacks.is_empty() {
+        errors.push(ParseError::MissingAttack);
+    }
+    if attacks.len() > attack_slots {
+        errors.push(ParseError::TooManyAttacks { max: attack_slots });
+    }
+    if protects.is_empty() {
+        errors.push(ParseError::MissingProtect);
+    }
+    if protects.len() > protect_slots {
+        errors.push(ParseError::TooManyProtects { max: protect_slots });
+    }
+
+    if errors.is_empty() {
+        Ok(WarriorMove { attacks, protects, ability: None, consumable: None })
+    } else {
+        Err(InputError { errors })
+    }
 }
 
-#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug)]
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone)]
 #[serde(crate = "near_sdk::serde")]
 pub struct BattleToSave {
     pub(crate) warrior_1: Warrior,
@@ -207,6 +409,45 @@ pub struct BattleToSave {
     pub(crate) warrior_1_last_protect: Option<String>,
     pub(crate) warrior_2_last_attack: Option<String>,
     pub(crate) warrior_2_last_protect: Option<String>,
+    pub(crate) format: BattleFormat,
+    pub(crate) round: u16,
+    pub(crate) duel: u8,
+    pub(crate) warrior_1_duel_wins: u8,
+    pub(crate) warrior_2_duel_wins: u8,
+    pub(crate) warrior_1_max_health: u16,
+    pub(crate) warrior_2_max_health: u16,
+    pub(crate) warrior_1_abilities: AbilityState,
+    pub(crate) warrior_2_abilities: AbilityState,
+    pub(crate) warrior_1_effects: Vec<StatusEffect>,
+    pub(crate) warrior_2_effects: Vec<StatusEffect>,
+}
+
+/// Stored battle of any layout, old layouts are converted when the battle is read
+#[derive(BorshSerialize, BorshDeserialize)]
+pub enum EBattleToSave {
+    V0(BattleToSaveV0),
+    V1(BattleToSaveV1),
+    V2(BattleToSaveV1<WarriorV1>),
+    V3(BattleToSaveV1<WarriorV2>),
+    V4(BattleToSaveV2),
+    V5(BattleToSaveV3),
+    V6(BattleToSaveV3<WarriorV5>),
+    Current(BattleToSave),
+}
+
+impl From<EBattleToSave> for BattleToSave {
+    fn from(e_battle_to_save: EBattleToSave) -> Self {
+        match e_battle_to_save {
+            EBattleToSave::V0(battle_to_save) => battle_to_save.into(),
+            EBattleToSave::V1(battle_to_save) => battle_to_save.into(),
+            EBattleToSave::V2(battle_to_save) => battle_to_save.into(),
+            EBattleToSave::V3(battle_to_save) => battle_to_save.into(),
+            EBattleToSave::V4(battle_to_save) => battle_to_save.into(),
+            EBattleToSave::V5(battle_to_save) => battle_to_save.into(),
+            EBattleToSave::V6(battle_to_save) => battle_to_save.into(),
+            EBattleToSave::Current(battle_to_save) => battle_to_save,
+        }
+    }
 }
 
 impl From<Battle> for BattleToSave {
@@ -223,14 +464,24 @@ impl From<Battle> for BattleToSave {
             warrior_1_last_protect: battle.warrior_1_last_protect,
             warrior_2_last_attack: battle.warrior_2_last_attack,
             warrior_2_last_protect: battle.warrior_2_last_protect,
+            format: battle.format,
+            round: battle.round,
+            duel: battle.duel,
+            warrior_1_duel_wins: battle.warrior_1_duel_wins,
+            warrior_2_duel_wins: battle.warrior_2_duel_wins,
+            warrior_1_max_health: battle.warrior_1_max_health,
+            warrior_2_max_health: battle.warrior_2_max_health,
+            warrior_1_abilities: battle.warrior_1_abilities,
+            warrior_2_abilities: battle.warrior_2_abilities,
+            warrior_1_effects: battle.warrior_1_effects,
+            warrior_2_effects: battle.warrior_2_effects,
         }
     }
 }
 
 impl From<BattleToSave> for Battle {
     fn from(battle_to_save: BattleToSave) -> Self {
-
-        let battle = Battle {
+        Battle {
             warrior_1: battle_to_save.warrior_1,
             warrior_2: battle_to_save.warrior_2,
             winner: battle_to_save.winner,
@@ -242,19 +493,41 @@ impl From<BattleToSave> for Battle {
             warrior_1_last_protect: battle_to_save.warrior_1_last_protect,
             warrior_2_last_attack: battle_to_save.warrior_2_last_attack,
             warrior_2_last_protect: battle_to_save.warrior_2_last_protect,
-        };
-
-        battle
+            format: battle_to_save.format,
+            round: battle_to_save.round,
+            duel: battle_to_save.duel,
+            warrior_1_duel_wins: battle_to_save.warrior_1_duel_wins,
+            warrior_2_duel_wins: battle_to_save.warrior_2_duel_wins,
+            warrior_1_max_health: battle_to_save.warrior_1_max_health,
+            warrior_2_max_health: battle_to_save.warrior_2_max_health,
+            warrior_1_abilities: battle_to_save.warrior_1_abilities,
+            warrior_2_abilities: battle_to_save.warrior_2_abilities,
+            warrior_1_effects: battle_to_save.warrior_1_effects,
+            warrior_2_effects: battle_to_save.warrior_2_effects,
+        }
     }
 }
 
 impl BattleToSave {
-    pub fn new(account_id_1: AccountId, account_id_2: AccountId, reward: Option<Balance>) -> BattleToSave {
-        let (warrior_1, warrior_2) = BattleToSave::create_two_warriors(account_id_1, account_id_2);
+    /// `profile` is the profile of the first warrior, the second one is a bot
+    pub fn new(
+        mut warrior_1: Warrior,
+        profile: &Profile,
+        warrior_2: Warrior,
+        reward: Option<Balance>,
+        format: BattleFormat,
+    ) -> BattleToSave {
+        warrior_1.apply_profile(profile);
 
         BattleToSave {
-            warrior_1,
-            warrior_2,
+            warrior_1_max_health: warrior_1.health,
+            warrior_2_max_health: warrior_2.health,
+            warrior_1_abilities: AbilityState::new(&warrior_1),
+            warrior_2_abilities: AbilityState::new(&warrior_2),
+            warrior_1_effects: vec![],
+            warrior_2_effects: vec![],
+            warrior_1: Warrior { id: 1, ..warrior_1 },
+            warrior_2: Warrior { id: 2, ..warrior_2 },
             winner: None,
             reward: reward.unwrap_or(0),
             last_action_timestamp: env::block_timestamp(),
@@ -264,33 +537,13 @@ impl BattleToSave {
             warrior_1_last_protect: None,
             warrior_2_last_attack: None,
             warrior_2_last_protect: None,
+            format,
+            round: 0,
+            duel: 0,
+            warrior_1_duel_wins: 0,
+            warrior_2_duel_wins: 0,
         }
     }
-
-    fn create_two_warriors(account_id_1: AccountId, account_id_2: AccountId) -> (Warrior, Warrior) {
-        (
-            Warrior {
-                id: 1,
-                account_id: Some(account_id_1),
-                strength: BASE_STRENGTH,
-                stamina: BASE_STAMINA,
-                agility: BASE_AGILITY,
-                intuition: BASE_INTUITION,
-                health: BASE_HEALTH,
-                defense: BASE_DEFENSE,
-            },
-            Warrior {
-                id: 2,
-                account_id: Some(account_id_2 + " (bot)"),
-                strength: BASE_STRENGTH,
-                stamina: BASE_STAMINA,
-                agility: BASE_AGILITY,
-                intuition: BASE_INTUITION,
-                health: BASE_HEALTH,
-                defense: BASE_DEFENSE,
-            },
-        )
-    }
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -299,32 +552,10 @@ pub enum BattleState {
 }
 
 impl Battle {
-    pub fn apply_actions(&mut self, actions: Vec<MoveData>) -> BattleToSave {
-        let parts_map = Part::VARIANTS;
-    
-        let warrior_1_attack = actions[0].part;
-        let warrior_1_protect = actions[1].part;
-        
-        let attack_seed = *random_seed().get(0).unwrap();
-        let protect_seed = *random_seed().get(1).unwrap();
-    
-        let mut attack_index = attack_seed / 50; 
-        let mut protect_index = protect_seed / 50; 
-    
-        if attack_index > 4 {
-            attack_index = attack_index - 4;
-        }
-        if protect_index > 4 {
-            protect_index = protect_index - 4;
-        }
-    
-        let warrior_2_attack = Part::from_str(parts_map[attack_index as usize]).unwrap();
-        let warrior_2_protect = Part::from_str(parts_map[protect_index as usize]).unwrap();
-    
-        let log_message = format!("Attack part: {:?}", parts_map[attack_index as usize]);
-        env::log(log_message.as_bytes());
-    
-        let log_message = format!("Protect part: {:?}", parts_map[protect_index as usize]);
+    pub fn apply_actions(&mut self, warrior_1_move: WarriorMove) -> BattleToSave {
+        let warrior_2_move = self.get_bot_move();
+
+        let log_message = format!("Bot move: {:?}", warrior_2_move);
         env::log(log_message.as_bytes());
     
         let log_message = format!("Block timestamp {}", env::block_timestamp());
@@ -336,138 +567,200 @@ impl Battle {
         if env::block_timestamp() > self.last_action_timestamp + MAX_MS_FOR_ACTION {
             self.warrior_1_missed_action = true;
 
-            let log_message = format!("Time for action is over");
-            env::log(log_message.as_bytes());
+            env::log(b"Time for action is over");
         } else {
             self.warrior_1_missed_action = false;
         }
 
-        let damage_to_2;
-        if self.warrior_1_missed_action {
-            damage_to_2 = 0;
-        } else {
-            if warrior_1_attack != warrior_2_protect || self.warrior_2_missed_action {
-                damage_to_2 = 3 * self.warrior_1.strength.clone() / 2;
-            } else {
-                // damage_to_2 = 2 * self.warrior_1.strength.clone() - self.warrior_2.defense.clone();
-                damage_to_2 = 3 * self.warrior_1.strength.clone() / 2 / 4;
+        let seed = random_seed();
+        let (effect_damage_to_1, warrior_1_stunned) = tick_effects(&mut self.warrior_1_effects);
+        let (effect_damage_to_2, warrior_2_stunned) = tick_effects(&mut self.warrior_2_effects);
+        let warrior_1_attacks = !self.warrior_1_missed_action && !warrior_1_stunned;
+        let warrior_2_attacks = !self.warrior_2_missed_action && !warrior_2_stunned;
+
+        let damage_to_2 = if warrior_1_attacks {
+            let crit_rolls = &seed[WARRIOR_1_CRIT_SEED_INDEX..WARRIOR_2_CRIT_SEED_INDEX];
+            for effect in get_hit_effects(&self.warrior_1, &warrior_1_move, &warrior_2_move, self.warrior_2_missed_action, crit_rolls) {
+                add_effect(&mut self.warrior_2_effects, effect);
             }
-        }
-    
-        let damage_to_1;
-        if self.warrior_2_missed_action {
-            damage_to_1 = 0;
+            get_damage(&self.warrior_1, &self.warrior_2, &warrior_1_move, &warrior_2_move,
+                       self.warrior_2_missed_action, crit_rolls)
         } else {
-            if warrior_2_attack != warrior_1_protect || self.warrior_1_missed_action {
-                damage_to_1 = 3 * self.warrior_2.strength.clone() / 2;
-            } else {
-                // damage_to_1 = 2 * self.warrior_2.strength.clone() - self.warrior_1.defense.clone();
-                damage_to_1 = 3 * self.warrior_2.strength.clone() / 2 / 4;
+            0
+        };
+
+        let damage_to_1 = if warrior_2_attacks {
+            let crit_rolls = &seed[WARRIOR_2_CRIT_SEED_INDEX..LOOT_SEED_INDEX];
+            for effect in get_hit_effects(&self.warrior_2, &warrior_2_move, &warrior_1_move, self.warrior_1_missed_action, crit_rolls) {
+                add_effect(&mut self.warrior_1_effects, effect);
             }
-        }
+            get_damage(&self.warrior_2, &self.warrior_1, &warrior_2_move, &warrior_1_move,
+                       self.warrior_1_missed_action, crit_rolls)
+        } else {
+            0
+        };
     
+        let warrior_1_ability = warrior_1_move.ability.filter(|_| warrior_1_attacks);
+        let warrior_2_ability = warrior_2_move.ability.filter(|_| warrior_2_attacks);
+        let (damage_to_1, damage_to_2) = self.apply_abilities(warrior_1_ability, warrior_2_ability, damage_to_1, damage_to_2);
+        let damage_to_1 = damage_to_1.saturating_add(effect_damage_to_1);
+        let damage_to_2 = damage_to_2.saturating_add(effect_damage_to_2);
+
         let log_message = format!("damage_to_1: {}", damage_to_1);
         env::log(log_message.as_bytes());
 
         let log_message = format!("damage_to_2: {}", damage_to_2);
         env::log(log_message.as_bytes());
 
-        if self.warrior_1.health.clone() > damage_to_1 && self.warrior_2.health.clone() > damage_to_2 {
-            let warrior_1_health = self.warrior_1.health.clone() - damage_to_1;
-            let warrior_2_health = self.warrior_2.health.clone() - damage_to_2;
-
-            let log_message = format!("Warrior 1 health: {}", warrior_1_health);
-            env::log(log_message.as_bytes());
-
-            let log_message = format!("Warrior 2 health: {}", warrior_2_health);
-            env::log(log_message.as_bytes());
-        
-            self.warrior_1.health = warrior_1_health;
-            self.warrior_2.health = warrior_2_health;
-            self.winner = Some(100);
-        
-            
-            BattleToSave {
-                warrior_1: self.warrior_1.clone(),
-                warrior_2: self.warrior_2.clone(),
-                winner: None,
-                reward: 0,
-                last_action_timestamp: env::block_timestamp(),
-                warrior_1_missed_action: self.warrior_1_missed_action,
-                warrior_2_missed_action: self.warrior_2_missed_action,
-                warrior_1_last_attack: Some(warrior_1_attack.as_str()),
-                warrior_1_last_protect: Some(warrior_1_protect.as_str()),
-                warrior_2_last_attack: Some(parts_map[attack_index as usize].to_string()),
-                warrior_2_last_protect: Some(parts_map[protect_index as usize].to_string()),
-            }
-        } else {
-            let mut is_warrior_1_dead = false;
-            let mut is_warrior_2_dead = false;
-            let mut winner = 0;
+        self.finish_round(damage_to_1, damage_to_2);
 
-            if self.warrior_1.health.clone() <= damage_to_1 {
-                is_warrior_1_dead = true;
-            }
+        let log_message = format!("Warrior 1 health: {}, warrior 2 health: {}", self.warrior_1.health, self.warrior_2.health);
+        env::log(log_message.as_bytes());
 
-            if self.warrior_2.health.clone() <= damage_to_2 {
-                is_warrior_2_dead = true;
-            }
+        self.last_action_timestamp = env::block_timestamp();
+        self.warrior_1_last_attack = Some(join_parts(&warrior_1_move.attacks));
+        self.warrior_1_last_protect = Some(join_parts(&warrior_1_move.protects));
+        self.warrior_2_last_attack = Some(join_parts(&warrior_2_move.attacks));
+        self.warrior_2_last_protect = Some(join_parts(&warrior_2_move.protects));
+
+        BattleToSave::from(self.clone())
+    }
 
-            if is_warrior_1_dead && !is_warrior_2_dead {
-                winner = self.warrior_2.id;
-                self.warrior_1.health = 0;
+    /// Spends energy on the used abilities, heals the warriors, puts the ability effects on the opponents
+    /// and returns the damage changed by the abilities
+    fn apply_abilities(
+        &mut self,
+        warrior_1_ability: Option<Ability>,
+        warrior_2_ability: Option<Ability>,
+        mut damage_to_1: u16,
+        mut damage_to_2: u16,
+    ) -> (u16, u16) {
+        if let Some(ability) = warrior_1_ability {
+            self.warrior_1_abilities.spend(ability, self.round);
+            let heal = ability.get_heal(self.warrior_1_max_health);
+            self.warrior_1.health = std::cmp::min(self.warrior_1.health.saturating_add(heal), self.warrior_1_max_health);
+            let (damage_done, damage_taken) = ability.apply_to_damage(damage_to_2, damage_to_1);
+            damage_to_2 = damage_done;
+            damage_to_1 = damage_taken;
+            if let Some(effect) = ability.get_opponent_effect() {
+                add_effect(&mut self.warrior_2_effects, effect);
             }
+        }
 
-            if !is_warrior_1_dead && is_warrior_2_dead {
-                winner = self.warrior_1.id;
-                self.warrior_2.health = 0;
+        if let Some(ability) = warrior_2_ability {
+            self.warrior_2_abilities.spend(ability, self.round);
+            let heal = ability.get_heal(self.warrior_2_max_health);
+            self.warrior_2.health = std::cmp::min(self.warrior_2.health.saturating_add(heal), self.warrior_2_max_health);
+            let (damage_done, damage_taken) = ability.apply_to_damage(damage_to_1, damage_to_2);
+            damage_to_1 = damage_done;
+            damage_to_2 = damage_taken;
+            if let Some(effect) = ability.get_opponent_effect() {
+                add_effect(&mut self.warrior_1_effects, effect);
             }
+        }
+
+        self.warrior_1_abilities.regenerate();
+        self.warrior_2_abilities.regenerate();
+
+        (damage_to_1, damage_to_2)
+    }
+
+    /// Random parts for all slots of the bot, protected parts are different
+    fn get_bot_move(&self) -> WarriorMove {
+        let seed = random_seed();
+        let parts_num = Part::ALL.len();
+
+        let attacks = (0..self.warrior_2.get_attack_slots())
+            .map(|index| Part::ALL[seed[BOT_ATTACK_SEED_INDEX + index] as usize % parts_num])
+            .collect();
 
-            BattleToSave {
-                warrior_1: self.warrior_1.clone(),
-                warrior_2: self.warrior_2.clone(),
-                winner: Some(winner),
-                reward: 0,
-                last_action_timestamp: env::block_timestamp(),
-                warrior_1_missed_action: self.warrior_1_missed_action,
-                warrior_2_missed_action: self.warrior_2_missed_action,
-                warrior_1_last_attack: Some(warrior_1_attack.as_str()),
-                warrior_1_last_protect: Some(warrior_1_protect.as_str()),
-                warrior_2_last_attack: Some(parts_map[attack_index as usize].to_string()),
-                warrior_2_last_protect: Some(parts_map[protect_index as usize].to_string()),
+        let first_protect_index = seed[BOT_PROTECT_SEED_INDEX] as usize;
+        let protects = (0..self.warrior_2.get_protect_slots())
+            .map(|index| Part::ALL[(first_protect_index + index) % parts_num])
+            .collect();
+
+        WarriorMove { attacks, protects, ability: None, consumable: None }
+    }
+
+    /// Applies the round damage and decides the winner by the battle format
+    fn finish_round(&mut self, damage_to_1: u16, damage_to_2: u16) {
+        self.warrior_1.health = self.warrior_1.health.saturating_sub(damage_to_1);
+        self.warrior_2.health = self.warrior_2.health.saturating_sub(damage_to_2);
+        self.round += 1;
+
+        let duel_winner = match (self.warrior_1.health, self.warrior_2.health) {
+            (0, 0) => Some(0),
+            (0, _) => Some(self.warrior_2.id),
+            (_, 0) => Some(self.warrior_1.id),
+            _ => None,
+        };
+
+        self.winner = match self.format {
+            BattleFormat::Elimination => duel_winner,
+            BattleFormat::BestOf { duels } => duel_winner.and_then(|duel_winner| self.finish_duel(duel_winner, duels)),
+            BattleFormat::Rounds { rounds } => duel_winner.or_else(|| {
+                if self.round >= rounds { Some(self.get_health_percentage_winner()) } else { None }
+            }),
+        };
+    }
+
+    /// Counts the duel win, restores health and clears effects for the next duel, returns the battle winner if the battle is over
+    fn finish_duel(&mut self, duel_winner: u32, duels: u8) -> Option<u32> {
+        self.duel += 1;
+        if duel_winner == self.warrior_1.id {
+            self.warrior_1_duel_wins += 1;
+        } else if duel_winner == self.warrior_2.id {
+            self.warrior_2_duel_wins += 1;
+        }
+
+        if self.warrior_1_duel_wins > duels / 2 {
+            Some(self.warrior_1.id)
+        } else if self.warrior_2_duel_wins > duels / 2 {
+            Some(self.warrior_2.id)
+        } else if self.duel >= duels {
+            match self.warrior_1_duel_wins.cmp(&self.warrior_2_duel_wins) {
+                std::cmp::Ordering::Greater => Some(self.warrior_1.id),
+                std::cmp::Ordering::Less => Some(self.warrior_2.id),
+                std::cmp::Ordering::Equal => Some(0),
             }
+        } else {
+            self.warrior_1.health = self.warrior_1_max_health;
+            self.warrior_2.health = self.warrior_2_max_health;
+            self.warrior_1_effects.clear();
+            self.warrior_2_effects.clear();
+            None
+        }
+    }
+
+    fn get_health_percentage_winner(&self) -> u32 {
+        // Compared as h1 / max1 vs h2 / max2 without division
+        let health_1 = self.warrior_1.health as u32 * std::cmp::max(self.warrior_2_max_health, 1) as u32;
+        let health_2 = self.warrior_2.health as u32 * std::cmp::max(self.warrior_1_max_health, 1) as u32;
+
+        match health_1.cmp(&health_2) {
+            std::cmp::Ordering::Greater => self.warrior_1.id,
+            std::cmp::Ordering::Less => self.warrior_2.id,
+            std::cmp::Ordering::Equal => 0,
         }
     }
 
     fn create_two_warriors(account_id_1: AccountId, account_id_2: AccountId) -> (Warrior, Warrior) {
         (
-            Warrior {
-                id: 1,
-                account_id: Some(account_id_1),
-                strength: BASE_STRENGTH,
-                stamina: BASE_STAMINA,
-                agility: BASE_AGILITY,
-                intuition: BASE_INTUITION,
-                health: BASE_HEALTH,
-                defense: BASE_DEFENSE,
-            },
-            Warrior {
-                id: 2,
-                account_id: Some(account_id_2),
-                strength: BASE_STRENGTH,
-                stamina: BASE_STAMINA,
-                agility: BASE_AGILITY,
-                intuition: BASE_INTUITION,
-                health: BASE_HEALTH,
-                defense: BASE_DEFENSE,
-            },
+            Warrior::new(1, Some(account_id_1)),
+            Warrior::new(2, Some(account_id_2)),
         )
     }
 
-    pub fn new(account_id_1: AccountId, account_id_2: AccountId, reward: Option<Balance>) -> Battle {
+    pub fn new(account_id_1: AccountId, account_id_2: AccountId, reward: Option<Balance>, format: BattleFormat) -> Battle {
         let (warrior_1, warrior_2) = Battle::create_two_warriors(account_id_1, account_id_2);
 
         Battle {
+            warrior_1_max_health: warrior_1.health,
+            warrior_2_max_health: warrior_2.health,
+            warrior_1_abilities: AbilityState::new(&warrior_1),
+            warrior_2_abilities: AbilityState::new(&warrior_2),
+            warrior_1_effects: vec![],
+            warrior_2_effects: vec![],
             warrior_1,
             warrior_2,
             winner: None,
@@ -479,6 +772,11 @@ impl Battle {
             warrior_1_last_protect: None,
             warrior_2_last_attack: None,
             warrior_2_last_protect: None,
+            format,
+            round: 0,
+            duel: 0,
+            warrior_1_duel_wins: 0,
+            warrior_2_duel_wins: 0,
         }
     }
 }
@@ -486,12 +784,218 @@ impl Battle {
 #[cfg(test)]
 mod test {
     use super::*;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::{testing_env, MockedBlockchain};
+    use proptest::prelude::*;
 
     #[test]
-    fn test_parse_move(/*params : &str, exp_result : Vec<MoveData>*/) {
-        let result = parse_move("Attack:Head Protect:Legs").ok().unwrap();
-    
-        assert_eq!(vec![MoveData::new(ActionType::Attack, Part::Head), MoveData::new(ActionType::Protect, Part::Legs)], result);
+    fn test_parse_move() {
+        let expected = WarriorMove { attacks: vec![Part::Head], protects: vec![Part::Legs], ability: None, consumable: None };
+
+        assert_eq!(parse_move("Attack:Head Protect:Legs", 1, 1), Ok(expected.clone()));
+        assert_eq!(parse_move(" Protect:Legs\tAttack:Head ", 1, 1), Ok(expected));
+    }
+
+    #[test]
+    fn test_parse_move_errors() {
+        let errors = |params: &str| parse_move(params, 1, 1).unwrap_err().errors;
+
+        assert_eq!(errors("Attack"), vec![
+            ParseError::WrongFormat { token: "Attack".to_string() },
+            ParseError::MissingAttack,
+            ParseError::MissingProtect,
+        ]);
+        assert_eq!(errors("XAttackY:Headache Protect:Legs"), vec![
+            ParseError::WrongAction { action: "XAttackY".to_string() },
+            ParseError::WrongPart { part: "Headache".to_string() },
+            ParseError::MissingAttack,
+        ]);
+        assert_eq!(errors("Attack:Head Attack:Neck Protect:Legs Protect:Legs"), vec![
+            ParseError::SameProtectedPart { part: Part::Legs },
+            ParseError::TooManyAttacks { max: 1 },
+        ]);
+    }
+
+    #[test]
+    fn test_warrior_move_slots() {
+        let mut warrior = Warrior::new(1, None);
+        warrior.attack_slots = 2;
+        let warrior_move = parse_move("Attack:Head Protect:Legs Attack:Chest", warrior.get_attack_slots(), 1).unwrap();
+        assert_eq!(warrior_move.attacks, vec![Part::Head, Part::Chest]);
+
+        let defender_move = WarriorMove { attacks: vec![Part::Head], protects: vec![Part::Head], ability: None, consumable: None };
+        warrior.strength = 20;
+        let no_crits = [99, 99];
+        assert_eq!(get_damage(&warrior, &warrior, &warrior_move, &defender_move, false, &no_crits), 45 / 4 + 30);
+    }
+
+    #[test]
+    fn test_part_damage() {
+        let mut attacker = Warrior::new(1, None);
+        attacker.strength = 20;
+        let mut defender = Warrior::new(2, None);
+        let defender_move = WarriorMove { attacks: vec![Part::Head], protects: vec![Part::Neck], ability: None, consumable: None };
+        let damage = |defender: &Warrior, part: Part, crit_roll: u8|
+            get_damage(&attacker, defender, &WarriorMove { attacks: vec![part], protects: vec![], ability: None, consumable: None }, &defender_move, false, &[crit_roll]);
+
+        assert_eq!(damage(&defender, Part::Chest, 99), 30);
+        assert_eq!(damage(&defender, Part::Head, 99), 45);
+        assert_eq!(damage(&defender, Part::Head, 0), 90);
+        assert_eq!(damage(&defender, Part::Neck, 99), 37 / 4);
+
+        let mut helmet = ItemAttributes::new(Place::Helmet);
+        helmet.armor = 100;
+        defender.apply_item(&helmet);
+        assert_eq!(damage(&defender, Part::Head, 99), 22);
+        assert_eq!(damage(&defender, Part::Chest, 99), 30);
+
+        defender.add_resistance(&Resistance { damage_type: DamageType::Blunt, value: 50 });
+        assert_eq!(damage(&defender, Part::Chest, 99), 15);
+    }
+
+    #[test]
+    fn test_move_input() {
+        let move_input: MoveInput = near_sdk::serde_json::from_str(r#"{"attack": "Head", "protect": "Legs"}"#).unwrap();
+        assert_eq!(move_input.to_warrior_move(1, 1), parse_move("Attack:Head Protect:Legs", 1, 1));
+
+        let move_input: MoveInput = near_sdk::serde_json::from_str(
+            r#"{"attack": "Head", "protect": "Legs", "extra_attacks": ["Chest"], "extra_protects": ["Legs"]}"#
+        ).unwrap();
+        assert_eq!(move_input.to_warrior_move(1, 2).unwrap_err().errors, vec![
+            ParseError::SameProtectedPart { part: Part::Legs },
+            ParseError::TooManyAttacks { max: 1 },
+        ]);
+
+        assert!(near_sdk::serde_json::from_str::<MoveInput>(r#"{"attack": "Tail", "protect": "Legs"}"#).is_err());
+    }
+
+    fn part_name() -> impl Strategy<Value = String> {
+        (0..Part::ALL.len()).prop_map(|index| Part::ALL[index].as_str())
+    }
+
+    proptest! {
+        #[test]
+        fn parse_move_never_panics(params in "\\PC*", attack_slots in 0..4usize, protect_slots in 0..4usize) {
+            let _ = parse_move(&params, attack_slots, protect_slots);
+        }
+
+        #[test]
+        fn parse_move_is_order_independent(attack in part_name(), protect in part_name(), separator in "[ \t\n]{1,3}") {
+            let attack_first = parse_move(&format!("Attack:{}{}Protect:{}", attack, separator, protect), 1, 1);
+            let protect_first = parse_move(&format!("{}Protect:{}{}Attack:{}", separator, protect, separator, attack), 1, 1);
+
+            prop_assert!(attack_first.is_ok());
+            prop_assert_eq!(attack_first, protect_first);
+        }
+
+        #[test]
+        fn parse_move_rejects_noise(attack in part_name(), noise in "[A-Za-z:]{1,3}") {
+            let prefixed = format!("{}Attack:{} Protect:Legs", noise, attack);
+            let suffixed = format!("Attack:{}{} Protect:Legs", attack, noise);
+
+            prop_assert!(parse_move(&prefixed, 1, 1).is_err());
+            prop_assert!(parse_move(&suffixed, 1, 1).is_err());
+        }
+    }
+
+    fn battle(format: BattleFormat) -> Battle {
+        testing_env!(VMContextBuilder::new().build());
+        Battle::new("alice".to_string(), "bob".to_string(), None, format)
+    }
+
+    #[test]
+    fn test_apply_abilities() {
+        let mut battle = battle(BattleFormat::Elimination);
+        battle.warrior_1.health = 1;
+
+        assert_eq!(battle.apply_abilities(Some(Ability::Heal), None, 3, 4), (3, 4));
+        assert_eq!(battle.warrior_1.health, 1 + BASE_HEALTH / 4);
+        assert_eq!(battle.apply_abilities(Some(Ability::PowerStrike), Some(Ability::Stun), 3, 4), (3, 8));
+        assert_eq!(battle.warrior_2_abilities.ready_at, vec![(Ability::Stun, Ability::Stun.cooldown())]);
+        assert_eq!(battle.warrior_1_effects, vec![StatusEffect { kind: StatusEffectKind::Stun, rounds: 1, strength: 0 }]);
+    }
+
+    #[test]
+    fn test_stunned_warrior_can_not_use_abilities() {
+        let mut battle = battle(BattleFormat::Elimination);
+        battle.warrior_1.health = 1;
+        battle.warrior_1.abilities.push(Ability::Heal);
+        battle.warrior_1_effects.push(StatusEffect { kind: StatusEffectKind::Stun, rounds: 1, strength: 0 });
+        let warrior_move = WarriorMove { ability: Some(Ability::Heal), ..parse_move("Attack:Legs Protect:Head", 1, 1).unwrap() };
+
+        battle.apply_actions(warrior_move);
+        assert!(battle.warrior_1.health <= 1);
+        assert!(battle.warrior_1_abilities.ready_at.is_empty());
+    }
+
+    #[test]
+    fn test_item_stun_does_not_lock_opponent() {
+        let mut battle = battle(BattleFormat::Elimination);
+        battle.warrior_1.health = 1_000;
+        battle.warrior_2.health = 1_000;
+        battle.warrior_1.hit_effects.push(StatusEffect { kind: StatusEffectKind::Stun, rounds: 5, strength: 0 });
+
+        for _ in 0..3 {
+            battle.apply_actions(parse_move("Attack:Legs Protect:Head", 1, 1).unwrap());
+            assert!(battle.warrior_2_effects.iter().all(|effect| effect.kind != StatusEffectKind::Stun));
+        }
+    }
+
+    #[test]
+    fn test_status_effects() {
+        let mut battle = battle(BattleFormat::Elimination);
+        battle.warrior_1.hit_effects.push(StatusEffect { kind: StatusEffectKind::Poison, rounds: 2, strength: 1 });
+        battle.warrior_2_effects.push(StatusEffect { kind: StatusEffectKind::Stun, rounds: 1, strength: 0 });
+        let warrior_move = parse_move("Attack:Legs Protect:Head", 1, 1).unwrap();
+
+        // The seed of the test environment is zero, so every hit is a crit
+        battle.apply_actions(warrior_move);
+        assert_eq!(battle.warrior_1.health, BASE_HEALTH);
+        assert_eq!(battle.warrior_2_effects, vec![
+            StatusEffect::bleed(&battle.warrior_1),
+            StatusEffect { kind: StatusEffectKind::Poison, rounds: 2, strength: 1 },
+        ]);
+
+        let health = battle.warrior_2.health;
+        battle.warrior_1.hit_effects.clear();
+        battle.apply_actions(parse_move("Attack:Legs Protect:Head", 1, 1).unwrap());
+        assert!(battle.warrior_2.health < health - 2);
+    }
+
+    #[test]
+    fn test_elimination_format() {
+        let mut battle = battle(BattleFormat::Elimination);
+
+        battle.finish_round(5, 0);
+        assert_eq!(battle.winner, None);
+        battle.finish_round(5, 10);
+        assert_eq!(battle.winner, Some(0));
+    }
+
+    #[test]
+    fn test_best_of_format() {
+        let mut battle = battle(BattleFormat::BestOf { duels: 3 });
+
+        battle.finish_round(0, BASE_HEALTH);
+        assert_eq!((battle.winner, battle.warrior_1_duel_wins), (None, 1));
+        assert_eq!(battle.warrior_2.health, BASE_HEALTH);
+
+        battle.finish_round(BASE_HEALTH, 0);
+        battle.finish_round(1, BASE_HEALTH);
+        assert_eq!(battle.winner, Some(1));
+        assert_eq!(battle.duel, 3);
+    }
+
+    #[test]
+    fn test_rounds_format() {
+        let mut battle = battle(BattleFormat::Rounds { rounds: 2 });
+        battle.warrior_2_max_health = 2 * BASE_HEALTH;
+        battle.warrior_2.health = 2 * BASE_HEALTH;
+
+        battle.finish_round(5, 5);
+        assert_eq!(battle.winner, None);
+        // 5 / 10 against 10 / 20
+        battle.finish_round(0, 5);
+        assert_eq!(battle.winner, Some(0));
     }
-    // TO DO: add tests for panics
 }
\ No newline at end of file
@@ -0,0 +1,129 @@
+use crate::*;
+use near_sdk::json_types::ValidAccountId;
+
+const HEALTH_POTION_PERCENTAGE: u32 = 30;
+const STRENGTH_ELIXIR_BONUS: u16 = 5;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(crate = "near_sdk::serde", rename_all = "snake_case")]
+pub enum Consumable {
+    /// Restores 30% of the max health
+    HealthPotion,
+    /// Adds strength for the rest of the battle
+    StrengthElixir,
+}
+
+/// Consumables of a player with their amounts
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Inventory {
+    pub consumables: Vec<(Consumable, u32)>,
+}
+
+impl Inventory {
+    pub fn get_amount(&self, consumable: Consumable) -> u32 {
+        self.consumables.iter()
+            .find(|(item, _)| *item == consumable)
+            .map_or(0, |(_, amount)| *amount)
+    }
+
+    fn add(&mut self, consumable: Consumable, amount: u32) {
+        match self.consumables.iter_mut().find(|(item, _)| *item == consumable) {
+            Some((_, current_amount)) => *current_amount = current_amount.saturating_add(amount),
+            None => self.consumables.push((consumable, amount)),
+        }
+    }
+
+    /// Removes one consumable, returns `false` if there is none
+    fn take(&mut self, consumable: Consumable) -> bool {
+        match self.consumables.iter().position(|(item, _)| *item == consumable) {
+            Some(index) => {
+                self.consumables[index].1 -= 1;
+                if self.consumables[index].1 == 0 {
+                    self.consumables.remove(index);
+                }
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl Battle {
+    /// Applies the consumable to the first warrior, the change lasts until the end of the battle
+    pub(crate) fn apply_consumable(&mut self, consumable: Consumable) {
+        match consumable {
+            Consumable::HealthPotion => {
+                let heal = (self.warrior_1_max_health as u32 * HEALTH_POTION_PERCENTAGE / 100) as u16;
+                self.warrior_1.health = std::cmp::min(self.warrior_1.health.saturating_add(heal), self.warrior_1_max_health);
+            }
+            Consumable::StrengthElixir => {
+                self.warrior_1.strength = self.warrior_1.strength.saturating_add(STRENGTH_ELIXIR_BONUS);
+            }
+        }
+    }
+}
+
+impl DeFight {
+    /// Takes the consumable from the inventory of the battle player
+    pub(crate) fn take_consumable(&mut self, account_id: &AccountId, consumable: Consumable) {
+        let mut inventory = self.get_inventory(account_id.clone());
+        assert!(inventory.take(consumable), "No {:?} in the inventory", consumable);
+        self.inventories.insert(account_id, &inventory);
+
+        emit_event("use_consumable", ConsumableEventData { account_id: account_id.clone(), consumable, amount: 1 });
+    }
+}
+
+#[near_bindgen]
+impl DeFight {
+    pub fn give_consumables(&mut self, account_id: ValidAccountId, consumable: Consumable, amount: u32) {
+        self.assert_owner();
+        assert!(amount > 0, "Amount must be positive");
+        let account_id: AccountId = account_id.into();
+        let initial_storage_usage = env::storage_usage();
+
+        let mut inventory = self.get_inventory(account_id.clone());
+        inventory.add(consumable, amount);
+        self.inventories.insert(&account_id, &inventory);
+        // The player pays for the own inventory
+        self.charge_storage(&account_id, initial_storage_usage);
+
+        emit_event("give_consumables", ConsumableEventData { account_id, consumable, amount });
+    }
+
+    pub fn get_inventory(&self, account_id: AccountId) -> Inventory {
+        self.inventories.get(&account_id).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::setup_contract;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::{testing_env, MockedBlockchain};
+
+    #[test]
+    fn test_use_consumable() {
+        let mut contract = setup_contract();
+        contract.give_consumables(accounts(1), Consumable::StrengthElixir, 2);
+
+        let mut battle = Battle::new(accounts(1).into(), accounts(2).into(), None, BattleFormat::Elimination);
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(1)).build());
+        contract.take_consumable(&accounts(1).into(), Consumable::StrengthElixir);
+        battle.apply_consumable(Consumable::StrengthElixir);
+
+        assert_eq!(battle.warrior_1.strength, BASE_STRENGTH + STRENGTH_ELIXIR_BONUS);
+        assert_eq!(contract.get_inventory(accounts(1).into()).get_amount(Consumable::StrengthElixir), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "No HealthPotion in the inventory")]
+    fn test_use_missing_consumable() {
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(1)).build());
+        let mut contract = DeFight::new();
+
+        contract.take_consumable(&accounts(1).into(), Consumable::HealthPotion);
+    }
+}
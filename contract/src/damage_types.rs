@@ -0,0 +1,117 @@
+use crate::*;
+
+const MAX_ITEM_RESISTANCE: u16 = 50;
+/// Resistances of all items together can't make a warrior immune
+const MAX_RESISTANCE: u16 = 75;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(crate = "near_sdk::serde", rename_all = "snake_case")]
+pub enum DamageType {
+    Slash,
+    /// Damage of unarmed warriors
+    Blunt,
+    Pierce,
+    Fire,
+    Frost,
+}
+
+/// Percentage of the damage type blocked by the item
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Resistance {
+    pub damage_type: DamageType,
+    pub value: u16,
+}
+
+impl ItemAttributes {
+    pub(crate) fn validate_damage_types(&self) -> Result<(), String> {
+        if self.damage_type.is_some() && !Place::Weapon1.accepts(self.place) {
+            return Err("Only weapons can have a damage type".to_string());
+        }
+        // Weapons deal the damage, the resistances are given by the worn items
+        if !self.resistances.is_empty() && Place::Weapon1.accepts(self.place) {
+            return Err("Weapons can't have resistances".to_string());
+        }
+
+        for resistance in &self.resistances {
+            if resistance.value > MAX_ITEM_RESISTANCE {
+                return Err(format!("Resistance can't be greater than {}", MAX_ITEM_RESISTANCE));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Warrior {
+    pub(crate) fn add_resistance(&mut self, resistance: &Resistance) {
+        match self.resistances.iter_mut().find(|current| current.damage_type == resistance.damage_type) {
+            Some(current) => current.value = std::cmp::min(current.value.saturating_add(resistance.value), MAX_RESISTANCE),
+            None => self.resistances.push(Resistance {
+                damage_type: resistance.damage_type,
+                value: std::cmp::min(resistance.value, MAX_RESISTANCE),
+            }),
+        }
+    }
+
+    /// Types of the weapons, the damage is split between them equally
+    pub fn get_damage_types(&self) -> Vec<DamageType> {
+        if self.damage_types.is_empty() {
+            vec![DamageType::Blunt]
+        } else {
+            self.damage_types.clone()
+        }
+    }
+
+    /// Percentage of the attacker damage that passes the resistances of the warrior
+    pub(crate) fn get_damage_taken_percentage(&self, attacker: &Warrior) -> u32 {
+        let damage_types = attacker.get_damage_types();
+        let total: u32 = damage_types.iter()
+            .map(|damage_type| {
+                let resistance = self.resistances.iter()
+                    .find(|resistance| resistance.damage_type == *damage_type)
+                    .map_or(0, |resistance| resistance.value);
+                100 - resistance as u32
+            })
+            .sum();
+
+        total / damage_types.len() as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_damage_taken_percentage() {
+        let mut attacker = Warrior::new(1, None);
+        let mut defender = Warrior::new(2, None);
+        assert_eq!(defender.get_damage_taken_percentage(&attacker), 100);
+
+        defender.add_resistance(&Resistance { damage_type: DamageType::Fire, value: 40 });
+        defender.add_resistance(&Resistance { damage_type: DamageType::Fire, value: 40 });
+        attacker.damage_types = vec![DamageType::Fire];
+        assert_eq!(defender.get_damage_taken_percentage(&attacker), 100 - MAX_RESISTANCE as u32);
+
+        attacker.damage_types.push(DamageType::Slash);
+        assert_eq!(defender.get_damage_taken_percentage(&attacker), (25 + 100) / 2);
+    }
+
+    #[test]
+    fn test_validate_damage_types() {
+        let mut attributes = ItemAttributes::new(Place::Helmet);
+        attributes.resistances.push(Resistance { damage_type: DamageType::Pierce, value: MAX_ITEM_RESISTANCE });
+        assert_eq!(attributes.validate(), Ok(()));
+
+        attributes.damage_type = Some(DamageType::Fire);
+        assert!(attributes.validate().is_err());
+
+        let mut weapon = ItemAttributes::new(Place::Weapon1);
+        weapon.damage_type = Some(DamageType::Fire);
+        assert_eq!(weapon.validate(), Ok(()));
+
+        weapon.resistances.push(Resistance { damage_type: DamageType::Frost, value: 10 });
+        assert_eq!(weapon.validate(), Err("Weapons can't have resistances".to_string()));
+    }
+}
@@ -0,0 +1,118 @@
+use crate::*;
+
+const BLEED_ROUNDS: u16 = 3;
+const MAX_ITEM_EFFECT_ROUNDS: u16 = 5;
+const MAX_ITEM_EFFECT_STRENGTH: u16 = 20;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(crate = "near_sdk::serde", rename_all = "snake_case")]
+pub enum StatusEffectKind {
+    /// Deals its strength every round, new bleeds add up
+    Bleed,
+    /// Deals its strength every round, a new poison only refreshes the stronger one
+    Poison,
+    /// The warrior attacks do no damage
+    Stun,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StatusEffect {
+    pub kind: StatusEffectKind,
+    /// Rounds left, the effect is applied at the start of each of them
+    pub rounds: u16,
+    pub strength: u16,
+}
+
+impl StatusEffect {
+    /// Bleeding caused by a crit of the attacker
+    pub(crate) fn bleed(attacker: &Warrior) -> StatusEffect {
+        StatusEffect {
+            kind: StatusEffectKind::Bleed,
+            rounds: BLEED_ROUNDS,
+            strength: std::cmp::max(attacker.strength / 4, 1),
+        }
+    }
+
+    /// Checks the effect given by an item on hit
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        if !self.is_item_effect() {
+            return Err(format!("{:?} can't be given by items", self.kind));
+        }
+        if self.rounds == 0 || self.rounds > MAX_ITEM_EFFECT_ROUNDS {
+            return Err(format!("Effect must last from 1 to {} rounds", MAX_ITEM_EFFECT_ROUNDS));
+        }
+        if self.strength > MAX_ITEM_EFFECT_STRENGTH {
+            return Err(format!("Effect strength can't be greater than {}", MAX_ITEM_EFFECT_STRENGTH));
+        }
+        Ok(())
+    }
+
+    /// Stun is given only by the ability with a cooldown, stuns on every hit would lock the opponent
+    pub(crate) fn is_item_effect(&self) -> bool {
+        self.kind != StatusEffectKind::Stun
+    }
+}
+
+/// An active stun is not refreshed
+pub(crate) fn add_effect(effects: &mut Vec<StatusEffect>, effect: StatusEffect) {
+    match effects.iter_mut().find(|active_effect| active_effect.kind == effect.kind) {
+        Some(_) if effect.kind == StatusEffectKind::Stun => {}
+        Some(active_effect) => {
+            active_effect.rounds = std::cmp::max(active_effect.rounds, effect.rounds);
+            active_effect.strength = if effect.kind == StatusEffectKind::Bleed {
+                active_effect.strength.saturating_add(effect.strength)
+            } else {
+                std::cmp::max(active_effect.strength, effect.strength)
+            };
+        }
+        None => effects.push(effect),
+    }
+}
+
+/// Applies the effects at the start of a round, returns their damage and whether the warrior is stunned
+pub(crate) fn tick_effects(effects: &mut Vec<StatusEffect>) -> (u16, bool) {
+    let mut damage: u16 = 0;
+    let mut stunned = false;
+
+    for effect in effects.iter_mut() {
+        match effect.kind {
+            StatusEffectKind::Bleed | StatusEffectKind::Poison => damage = damage.saturating_add(effect.strength),
+            StatusEffectKind::Stun => stunned = true,
+        }
+        effect.rounds -= 1;
+    }
+    effects.retain(|effect| effect.rounds > 0);
+
+    (damage, stunned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tick_effects() {
+        let mut effects = vec![];
+        add_effect(&mut effects, StatusEffect { kind: StatusEffectKind::Bleed, rounds: 2, strength: 1 });
+        add_effect(&mut effects, StatusEffect { kind: StatusEffectKind::Bleed, rounds: 1, strength: 2 });
+        add_effect(&mut effects, StatusEffect { kind: StatusEffectKind::Poison, rounds: 1, strength: 4 });
+        add_effect(&mut effects, StatusEffect { kind: StatusEffectKind::Poison, rounds: 1, strength: 1 });
+        add_effect(&mut effects, StatusEffect { kind: StatusEffectKind::Stun, rounds: 1, strength: 0 });
+
+        assert_eq!(tick_effects(&mut effects), (3 + 4, true));
+        assert_eq!(tick_effects(&mut effects), (3, false));
+        assert_eq!(tick_effects(&mut effects), (0, false));
+        assert!(effects.is_empty());
+    }
+
+    #[test]
+    fn test_stun_effect() {
+        let stun = StatusEffect { kind: StatusEffectKind::Stun, rounds: 1, strength: 0 };
+        assert!(stun.validate().is_err());
+
+        let mut effects = vec![stun.clone()];
+        add_effect(&mut effects, StatusEffect { rounds: 3, ..stun.clone() });
+        assert_eq!(effects, vec![stun]);
+    }
+}
@@ -0,0 +1,70 @@
+use crate::*;
+
+const EVENT_STANDARD: &str = "defight";
+const EVENT_VERSION: &str = "1.0.0";
+
+#[derive(Serialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct EquipmentEventData {
+    pub account_id: AccountId,
+    pub place: Place,
+    pub token: Option<EquippedToken>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct LootDropEventData {
+    pub account_id: AccountId,
+    pub tier: String,
+    pub token_id: TokenId,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct LevelUpEventData {
+    pub account_id: AccountId,
+    pub level: u16,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StatsEventData {
+    pub account_id: AccountId,
+    pub stats: StatPoints,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ConsumableEventData {
+    pub account_id: AccountId,
+    pub consumable: Consumable,
+    pub amount: u32,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BattleArchiveEventData {
+    pub battle_id: BattleId,
+    pub battle: BattleToSave,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+struct EventLog<'a, T: Serialize> {
+    standard: &'a str,
+    version: &'a str,
+    event: &'a str,
+    data: [T; 1],
+}
+
+/// Logs an event in the NEP-297 format: `EVENT_JSON:{"standard", "version", "event", "data"}`.
+/// The data is serialized directly, `serde_json::Value` doesn't support `u128` balances.
+pub(crate) fn emit_event<T: Serialize>(event: &str, data: T) {
+    let log_message = near_sdk::serde_json::to_string(&EventLog {
+        standard: EVENT_STANDARD,
+        version: EVENT_VERSION,
+        event,
+        data: [data],
+    }).unwrap();
+    env::log(format!("EVENT_JSON:{}", log_message).as_bytes());
+}
@@ -0,0 +1,112 @@
+use crate::*;
+use near_contract_standards::non_fungible_token::metadata::{
+    NFTContractMetadata, NonFungibleTokenMetadataProvider, NFT_METADATA_SPEC,
+};
+use near_contract_standards::non_fungible_token::refund_deposit;
+use near_sdk::json_types::ValidAccountId;
+use near_sdk::PromiseOrValue;
+use std::convert::TryInto;
+
+near_contract_standards::impl_non_fungible_token_core!(DeFight, tokens);
+near_contract_standards::impl_non_fungible_token_approval!(DeFight, tokens);
+near_contract_standards::impl_non_fungible_token_enumeration!(DeFight, tokens);
+
+pub(crate) fn default_nft_metadata() -> NFTContractMetadata {
+    NFTContractMetadata {
+        spec: NFT_METADATA_SPEC.to_string(),
+        name: "DeFight".to_string(),
+        symbol: "DEFIGHT".to_string(),
+        icon: None,
+        base_uri: None,
+        reference: None,
+        reference_hash: None,
+    }
+}
+
+/// `mint` of the standard implementation is allowed only for the contract itself,
+/// items and warriors are minted with `nft_mint_item` and `nft_mint_warrior`
+pub(crate) fn new_non_fungible_token() -> NonFungibleToken {
+    NonFungibleToken::new(
+        StorageKey::NonFungibleToken,
+        env::current_account_id().try_into().unwrap(),
+        Some(StorageKey::TokenMetadata),
+        Some(StorageKey::Enumeration),
+        Some(StorageKey::Approval),
+    )
+}
+
+#[near_bindgen]
+impl NonFungibleTokenMetadataProvider for DeFight {
+    fn nft_metadata(&self) -> NFTContractMetadata {
+        self.metadata.get().unwrap()
+    }
+}
+
+#[near_bindgen]
+impl DeFight {
+    /// Same as the standard `mint` but without the predecessor and the attached deposit checks
+    pub(crate) fn internal_mint(&mut self, owner_id: &AccountId, metadata: TokenMetadata) -> Token {
+        let token_id = self.next_token_id.to_string();
+        self.next_token_id += 1;
+
+        self.tokens.owner_by_id.insert(&token_id, owner_id);
+
+        if let Some(token_metadata_by_id) = &mut self.tokens.token_metadata_by_id {
+            token_metadata_by_id.insert(&token_id, &metadata);
+        }
+
+        if let Some(tokens_per_owner) = &mut self.tokens.tokens_per_owner {
+            let mut token_ids = tokens_per_owner.get(owner_id).unwrap_or_else(|| {
+                UnorderedSet::new(StorageKey::TokensPerOwner { account_hash: env::sha256(owner_id.as_bytes()) })
+            });
+            token_ids.insert(&token_id);
+            tokens_per_owner.insert(owner_id, &token_ids);
+        }
+
+        let approved_account_ids = self.tokens.approvals_by_id.as_ref().map(|_| HashMap::new());
+
+        Token { token_id, owner_id: owner_id.clone(), metadata: Some(metadata), approved_account_ids }
+    }
+
+    /// Mints an item with natively stored attributes. They are also copied to the `extra` metadata field.
+    pub(crate) fn internal_mint_item(&mut self, owner_id: &AccountId, mut metadata: TokenMetadata, attributes: ItemAttributes) -> Token {
+        metadata.extra = Some(near_sdk::serde_json::to_string(&attributes).unwrap());
+        let token = self.internal_mint(owner_id, metadata);
+        self.native_items.insert(&token.token_id, &EItemAttributes::Current(attributes));
+
+        token
+    }
+
+    #[payable]
+    pub fn nft_mint_item(&mut self, receiver_id: ValidAccountId, metadata: TokenMetadata, attributes: ItemAttributes) -> Token {
+        self.assert_owner();
+        if let Err(e) = attributes.validate() {
+            env::panic(format!("Wrong item attributes: {}", e).as_bytes());
+        }
+        let initial_storage_usage = env::storage_usage();
+
+        let token = self.internal_mint_item(receiver_id.as_ref(), metadata, attributes);
+
+        refund_deposit(env::storage_usage() - initial_storage_usage);
+        token
+    }
+
+    #[payable]
+    pub fn nft_mint_warrior(&mut self, receiver_id: ValidAccountId, metadata: TokenMetadata) -> Token {
+        self.assert_owner();
+        let initial_storage_usage = env::storage_usage();
+
+        let token = self.internal_mint(receiver_id.as_ref(), metadata);
+
+        refund_deposit(env::storage_usage() - initial_storage_usage);
+        token
+    }
+
+    pub fn get_item_attributes(&self, token_id: TokenId) -> Option<ItemAttributes> {
+        self.get_native_item(&token_id)
+    }
+
+    pub(crate) fn is_native_token_owner(&self, account_id: &AccountId, token_id: &TokenId) -> bool {
+        self.tokens.owner_by_id.get(token_id).as_ref() == Some(account_id)
+    }
+}
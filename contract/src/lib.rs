@@ -1,26 +1,56 @@
-use near_sdk::{AccountId, Balance, PanicOnDefault, BorshStorageKey, log, Timestamp, PromiseResult, Promise};
+use near_sdk::{AccountId, Balance, Gas, PanicOnDefault, BorshStorageKey, log, Timestamp, PromiseResult, Promise};
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::{LookupMap, UnorderedMap, UnorderedSet};
+use near_sdk::collections::{LazyOption, LookupMap, UnorderedMap, UnorderedSet};
 use near_sdk::{env, near_bindgen};
 use near_sdk::serde::{Deserialize, Serialize};
-use near_sdk::json_types::U128;
-use near_contract_standards::non_fungible_token::{TokenId, Token};
-use near_contract_standards::non_fungible_token::metadata::{
-  NFTContractMetadata, NonFungibleTokenMetadataProvider, TokenMetadata, NFT_METADATA_SPEC,
-};
+use near_contract_standards::non_fungible_token::{NonFungibleToken, TokenId, Token};
+use near_contract_standards::non_fungible_token::metadata::{NFTContractMetadata, TokenMetadata};
 use std::collections::HashMap;
 
 pub use warrior::Warrior;
-pub use battle::{Battle, BattleToSave, EBattleConfig, InputError, parse_move, ParseError, BattleState};
-pub use stats::{Stats, EStats};
+pub use ability::{Ability, AbilityState};
+pub use consumables::{Consumable, Inventory};
+pub use effects::{StatusEffect, StatusEffectKind};
+pub use damage_types::{DamageType, Resistance};
+pub use weapons::WeaponCategory;
+pub use sets::{ItemSet, ItemSetPiece, SetBonus};
+pub use battle::{Battle, BattleFormat, BattleSettings, BattleToSave, WarriorMove, MoveInput, EBattleToSave, EBattleConfig, InputError, parse_move, ParseError, Part, BattleState};
+pub use stats::{Stats, StatsV0, EStats};
 pub use nft::*;
 pub use crate::callbacks::*;
+pub use events::*;
+pub use loadout::{Loadout, ELoadout, BotDifficulty};
+pub use loot::{LootConfig, ELootConfig, LootDrops, LootItem, RarityTier};
+pub use progress::*;
+pub use profile::{BattleView, EProfile, Profile, StatPoints};
+pub use storage::StorageAccount;
+pub use archive::BattleSummary;
+pub use migration::DeFightV0;
+pub use upgrade::StagedUpgradeView;
 
 mod warrior;
 mod battle;
 mod stats;
 mod callbacks;
 mod nft;
+mod events;
+mod loadout;
+mod items;
+mod loot;
+mod progress;
+mod profile;
+mod storage;
+mod archive;
+mod migration;
+mod upgrade;
+mod ability;
+mod consumables;
+mod effects;
+mod damage_types;
+mod weapons;
+mod sets;
+#[cfg(test)]
+mod test_utils;
 
 type BattleId = u64;
 
@@ -33,8 +63,20 @@ const BASE_DEFENSE: u16 = 1;
 
 const MAX_MS_FOR_ACTION: u64 = 60_000_000_000;
 
+// Bytes of the random seed used in a round, the ranges don't overlap so the rolls are independent
+const BOT_ATTACK_SEED_INDEX: usize = 0;
+const BOT_PROTECT_SEED_INDEX: usize = BOT_ATTACK_SEED_INDEX + warrior::MAX_ATTACK_SLOTS as usize;
+const WARRIOR_1_CRIT_SEED_INDEX: usize = BOT_PROTECT_SEED_INDEX + 1;
+const WARRIOR_2_CRIT_SEED_INDEX: usize = WARRIOR_1_CRIT_SEED_INDEX + warrior::MAX_ATTACK_SLOTS as usize;
+/// Drop, tier and item rolls of 4 bytes each
+const LOOT_SEED_INDEX: usize = WARRIOR_2_CRIT_SEED_INDEX + warrior::MAX_ATTACK_SLOTS as usize;
+
+const GAS_FOR_NFT_TOKEN: Gas = 10_000_000_000_000;
+const GAS_FOR_RESOLVE_OWNER_TOKENS: Gas = 30_000_000_000_000;
+
 near_sdk::setup_alloc!();
 
+/// The variant index is the storage prefix of the collection, so new variants are only appended
 #[derive(BorshSerialize, BorshStorageKey)]
 enum StorageKey {
     OwnerIds,
@@ -47,6 +89,26 @@ enum StorageKey {
     Affiliates {account_id: AccountId},
     TotalRewards {account_id: AccountId},
     TotalAffiliateRewards{ account_id: AccountId},
+    NftContracts,
+    Loadouts,
+    NonFungibleToken,
+    TokenMetadata,
+    Enumeration,
+    Approval,
+    NftMetadata,
+    NativeItems,
+    TokensPerOwner { account_hash: Vec<u8> },
+    LootConfig,
+    LootDrops,
+    Profiles,
+    Nicknames,
+    StorageAccounts,
+    BattleSummaries,
+    BattlesV1,
+    WarriorsEquipmentV1,
+    Inventories,
+    ItemSets,
+    ItemSetsByPiece,
 }
 
 #[derive(PartialEq)]
@@ -64,35 +126,79 @@ pub enum UpdateStatsAction {
 #[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
 pub struct DeFight {
     owner_ids: UnorderedSet<AccountId>,
-    tokens_series: UnorderedMap<TokenId, TokenSeriesJson>,
-    battles: LookupMap<BattleId, BattleToSave>,
+    nft_contracts: UnorderedMap<AccountId, NftContractKind>,
+    tokens_series: UnorderedMap<TokenSeriesKey, ETokenSeries>,
+    battles: LookupMap<BattleId, EBattleToSave>,
     available_warriors: UnorderedMap<AccountId, EBattleConfig>,
-    warriors_equipment: LookupMap<AccountId, EquipmentConfig>,
+    warriors_equipment: LookupMap<AccountId, EEquipmentConfig>,
+    loadouts: LookupMap<AccountId, ELoadout>,
     stats: UnorderedMap<AccountId, EStats>,
     available_battles: UnorderedMap<BattleId, (AccountId, AccountId)>,
     next_battle_id: BattleId,
     service_fee: Balance,
+    tokens_series_version: u64,
+    tokens: NonFungibleToken,
+    metadata: LazyOption<NFTContractMetadata>,
+    native_items: LookupMap<TokenId, EItemAttributes>,
+    next_token_id: u64,
+    loot_config: LazyOption<ELootConfig>,
+    loot_drops: LookupMap<AccountId, LootDrops>,
+    profiles: LookupMap<AccountId, EProfile>,
+    nicknames: LookupMap<String, AccountId>,
+    storage_accounts: LookupMap<AccountId, StorageAccount>,
+    battle_summaries: LookupMap<BattleId, BattleSummary>,
+    battle_archive_age: Timestamp,
+    inventories: LookupMap<AccountId, Inventory>,
+    item_sets: UnorderedMap<String, ItemSet>,
+    /// Ids of the sets of every piece, so a warrior build reads only the sets of its equipment
+    item_sets_by_piece: LookupMap<ItemSetPiece, Vec<String>>,
 }
 
 #[near_bindgen]
 impl DeFight {
     #[init]
     pub fn new() -> Self {
-        let mut this = Self {
+        let mut this = DeFight::new_state();
+
+        this.owner_ids.insert(&env::predecessor_account_id());
+        this.nft_contracts.insert(&PARAS_CONTRACT_ID.to_string(), &NftContractKind::Paras);
+        migration::write_state_version();
+
+        this
+    }
+}
+
+impl DeFight {
+    /// State with empty collections, migrations reuse it for the fields missing in the old state
+    pub(crate) fn new_state() -> Self {
+        Self {
             owner_ids: UnorderedSet::new(StorageKey::OwnerIds),
+            nft_contracts: UnorderedMap::new(StorageKey::NftContracts),
             tokens_series: UnorderedMap::new(StorageKey::TokensSeries),
-            battles: LookupMap::new(StorageKey::Battles),
+            battles: LookupMap::new(StorageKey::BattlesV1),
             available_warriors: UnorderedMap::new(StorageKey::AvailableWarriors),
-            warriors_equipment: LookupMap::new(StorageKey::WarriorsEquipment),
+            warriors_equipment: LookupMap::new(StorageKey::WarriorsEquipmentV1),
+            loadouts: LookupMap::new(StorageKey::Loadouts),
             stats: UnorderedMap::new(StorageKey::Stats),
             available_battles: UnorderedMap::new(StorageKey::AvailableBattles),
             next_battle_id: 0,
             service_fee: 0,
-        };
-
-        this.owner_ids.insert(&env::predecessor_account_id());
-
-        this
+            tokens_series_version: 0,
+            tokens: items::new_non_fungible_token(),
+            metadata: LazyOption::new(StorageKey::NftMetadata, Some(&items::default_nft_metadata())),
+            native_items: LookupMap::new(StorageKey::NativeItems),
+            next_token_id: 0,
+            loot_config: LazyOption::new(StorageKey::LootConfig, None),
+            loot_drops: LookupMap::new(StorageKey::LootDrops),
+            profiles: LookupMap::new(StorageKey::Profiles),
+            nicknames: LookupMap::new(StorageKey::Nicknames),
+            storage_accounts: LookupMap::new(StorageKey::StorageAccounts),
+            battle_summaries: LookupMap::new(StorageKey::BattleSummaries),
+            battle_archive_age: archive::DEFAULT_BATTLE_ARCHIVE_AGE,
+            inventories: LookupMap::new(StorageKey::Inventories),
+            item_sets: UnorderedMap::new(StorageKey::ItemSets),
+            item_sets_by_piece: LookupMap::new(StorageKey::ItemSetsByPiece),
+        }
     }
 }
 
@@ -110,7 +216,7 @@ impl DeFight {
         if let Some(stats) = self.stats.get(account_id) {
             stats.into()
         } else {
-            Stats::new(&account_id)
+            Stats::new(account_id)
         }
     }
 
@@ -161,7 +267,7 @@ impl DeFight {
     }
 
     pub fn get_battle(&self, battle_id: &BattleId) -> BattleToSave {
-        let battle = self.battles.get(battle_id).expect("Battle not found");
+        let battle = self.get_saved_battle(battle_id).expect("Battle not found");
 
         let log_message = format!("Battle state: {:?}", battle);
         env::log(log_message.as_bytes());
@@ -177,211 +283,484 @@ impl DeFight {
         assert_eq!(battles_already_started.len(), 0, "Another battle already started");
     }
 
-    pub(crate) fn is_token_equipped(&self, equipment: &EquipmentConfig, place: &str, token_id: &TokenId) -> bool {
-        match place {
-            "helmet" => equipment.helmet == Some(token_id.to_string()),
-            "armor" => equipment.armor == Some(token_id.to_string()),
-            "gloves" => equipment.gloves == Some(token_id.to_string()),
-            "bracers" => equipment.bracers == Some(token_id.to_string()),
-            "shoulder_pads" => equipment.shoulder_pads == Some(token_id.to_string()),
-            "leggings" => equipment.leggings == Some(token_id.to_string()),
-            "boots" => equipment.boots == Some(token_id.to_string()),
-            "amulet" => equipment.amulet == Some(token_id.to_string()),
-            "weapon" => equipment.weapon_1 == Some(token_id.to_string()) || equipment.weapon_2 == Some(token_id.to_string()),
-            _ => false
-        }
-    }
-
+    #[private]
     #[near_sdk::serializer(borsh)]
-    pub fn resolve_paras_tokens(
+    pub fn resolve_owner_tokens(
         &mut self,
         account_id: String,
         referrer_id: Option<String>,
+        settings: BattleSettings,
+        tokens: Vec<EquippedToken>,
     ) -> BattleId {
-        let log_message = format!("Get tokens cross-contract callback");
-        env::log(log_message.as_bytes());
-
-        match env::promise_result(0) {
-            PromiseResult::NotReady => unreachable!(),
-            PromiseResult::Failed => env::panic(b"Unable to get user tokens"),
-            PromiseResult::Successful(result) => {
-                let battle_id = self.next_battle_id;
-            
-                let mut battle = BattleToSave::new(account_id.clone(), account_id.clone(), None);
-
-                let tokens = near_sdk::serde_json::from_slice::<Vec<Token>>(&result).unwrap();
-
-                let log_message = format!("User tokens: {:?}", tokens);
-                env::log(log_message.as_bytes());
-                
-                let warrior_tokens: Vec<_> = tokens.iter().filter(|x| self.tokens_series.get(&x.token_id.split(":").collect::<Vec<&str>>()[0].to_string()).is_some()).collect();
-
-                let log_message = format!("token_series_id: {:?}", warrior_tokens);
-                env::log(log_message.as_bytes());
-                
-                if let Some(equipment) = self.warriors_equipment.get(&account_id) {
-                    let log_message = format!("equipment: {:?}", equipment);
+        env::log(b"Get tokens cross-contract callback");
+        self.migrate_legacy_equipment(&account_id);
+        let initial_storage_usage = env::storage_usage();
+
+        let mut not_owned_tokens = vec![];
+
+        for (index, token) in tokens.into_iter().enumerate() {
+            match env::promise_result(index as u64) {
+                PromiseResult::NotReady => unreachable!(),
+                PromiseResult::Failed => {
+                    // The token stays equipped, a failed contract shouldn't block battles
+                    let log_message = format!("Unable to verify token {} of {}", token.token_id, token.contract_id);
                     env::log(log_message.as_bytes());
+                },
+                PromiseResult::Successful(result) => {
+                    match near_sdk::serde_json::from_slice::<Option<Token>>(&result) {
+                        Ok(Some(owned_token)) if owned_token.owner_id == account_id => {},
+                        Ok(_) => not_owned_tokens.push(token),
+                        Err(_) => {
+                            let log_message = format!("Unable to parse token {} of {}", token.token_id, token.contract_id);
+                            env::log(log_message.as_bytes());
+                        },
+                    }
+                },
+            }
+        }
 
-                    for token in warrior_tokens {
-                        let token_series_id = token.token_id.split(":").collect::<Vec<&str>>()[0].to_string();
-                        if let Some(token_series_json) = self.tokens_series.get(&token_series_id) {
+        let battle_id = self.next_battle_id;
 
-                            let metadata = &token_series_json.metadata;
-                            let log_message = format!("metadata: {:?}", metadata);
-                            env::log(log_message.as_bytes());
+        let warrior = self.confirm_ownership(&account_id, &not_owned_tokens);
+        let bot = DeFight::build_bot(&account_id, settings.bot_difficulty);
+        let profile = self.get_profile(&account_id);
+        let battle = BattleToSave::new(warrior, &profile, bot, None, settings.format);
 
-                            if let Some(extra) = &metadata.extra {
-                                let log_message = format!("extra: {:?}", extra);
-                                env::log(log_message.as_bytes());
-
-                                let params = extra.split(",").collect::<Vec<&str>>();
-                                let place = params[0].to_string()
-                                    .split(":").collect::<Vec<&str>>()[1].to_string();
-
-                                let log_message = format!("Place: {:?}", place);
-                                env::log(log_message.as_bytes());
-
-                                if self.is_token_equipped(&equipment, &place, &token.token_id) {
-                                    let log_message = format!("equipped_tokens: {:?}", token);
-                                    env::log(log_message.as_bytes());
-
-                                    for param in params {
-                                        if param.contains("strength") {
-                                            let strength = param.split(":").collect::<Vec<&str>>()[1].parse::<u16>().unwrap();
-                                            battle.warrior_1.strength += strength;
-                                            battle.warrior_2.strength += strength;
-                                            battle.warrior_1.defense += strength / 2;
-                                            battle.warrior_2.defense += strength / 2;
-                                        }
-                                        if param.contains("stamina") {
-                                            let stamina = param.split(":").collect::<Vec<&str>>()[1].parse::<u16>().unwrap();
-                                            battle.warrior_1.stamina += stamina;
-                                            battle.warrior_2.stamina += stamina;
-                                            battle.warrior_1.health += stamina*10;
-                                            battle.warrior_2.health += stamina*10;
-                                        }
-                                        if param.contains("agility") {
-                                            let agility = param.split(":").collect::<Vec<&str>>()[1].parse::<u16>().unwrap();
-                                            battle.warrior_1.agility += agility;
-                                            battle.warrior_2.agility += agility;
-                                            battle.warrior_1.defense += agility/2;
-                                            battle.warrior_2.defense += agility/2;
-                                        }
-                                        if param.contains("intuition") {
-                                            let intuition = param.split(":").collect::<Vec<&str>>()[1].parse::<u16>().unwrap();
-                                            battle.warrior_1.intuition += intuition;
-                                            battle.warrior_2.intuition += intuition;
-                                            battle.warrior_1.defense += intuition/2;
-                                            battle.warrior_2.defense += intuition/2;
-                                        }
-                                    }
-                                }
-                            }                        
-                            
-                        }       
-                    }
-                }
+        self.save_battle(&battle_id, &battle);
+        self.next_battle_id += 1;
 
-                self.battles.insert(&battle_id, &battle);
-                self.next_battle_id += 1;
-            
-                self.add_referral(&account_id, &referrer_id);
-                self.update_stats(&account_id, UpdateStatsAction::AddBattle, None, None);
+        self.add_referral(&account_id, &referrer_id);
+        self.update_stats(&account_id, UpdateStatsAction::AddBattle, None, None);
 
-                battle_id
-            },
-          }
+        // The player pays for the battle, the own stats and the referrer affiliates
+        self.charge_storage(&account_id, initial_storage_usage);
+
+        battle_id
     }
 
-    pub fn start_battle(&mut self, opponent_id: Option<AccountId>, referrer_id: Option<AccountId>) -> Promise {
-        if let Some(_opponent) = self.available_warriors.get(&opponent_id.unwrap_or("".to_string())) {
+    pub fn start_battle(
+        &mut self,
+        opponent_id: Option<AccountId>,
+        referrer_id: Option<AccountId>,
+        bot_difficulty: Option<BotDifficulty>,
+        format: Option<BattleFormat>,
+    ) -> Promise {
+        if let Some(_opponent) = self.available_warriors.get(&opponent_id.unwrap_or_default()) {
             panic!("PvP mode is not ready yet");
         } else {
             let account_id = env::predecessor_account_id();
 
             self.is_battle_started(&account_id);
-
-            // Initiating receiver's call and the callback
-            let battle_id = ext_paras_receiver::nft_tokens_for_owner(
-                env::signer_account_id(),
-                None,
-                None,
-                &"paras-token-v2.testnet".to_string(), //contract account to make the call to
-                0, //attached deposit
-                70_000_000_000_000,
-            )
-
-            //we then resolve the promise and call nft_resolve_transfer on our own contract
-            .then(ext_self::resolve_paras_tokens(
+            self.assert_storage_registered(&account_id);
+            let settings = BattleSettings {
+                bot_difficulty: bot_difficulty.unwrap_or_default(),
+                format: format.unwrap_or_default(),
+            };
+            settings.format.assert_valid();
+
+            // Native items are checked without cross-contract calls
+            let tokens = self.get_equipment(&account_id).unwrap_or_default().external_tokens();
+
+            // Requesting the owner of every equipped token
+            let tokens_promise = tokens.iter()
+                .map(|token| ext_nft::nft_token(
+                    token.token_id.clone(),
+                    &token.contract_id, //contract account to make the call to
+                    0, //attached deposit
+                    GAS_FOR_NFT_TOKEN,
+                ))
+                .reduce(|promise, next_promise| promise.and(next_promise));
+
+            let resolve_promise = ext_self::resolve_owner_tokens(
                 account_id,
                 referrer_id,
+                settings,
+                tokens,
                 &env::current_account_id(), //contract account to make the call to
                 0, //attached deposit
-                30_000_000_000_000, //GAS attached to the call
-            ));
+                GAS_FOR_RESOLVE_OWNER_TOKENS, //GAS attached to the call
+            );
 
-            battle_id
+            match tokens_promise {
+                Some(tokens_promise) => tokens_promise.then(resolve_promise),
+                None => resolve_promise,
+            }
         }
     }
 
     #[result_serializer(borsh)]
-    pub fn make_action(&mut self, battle_id: BattleId, params: String) {
+    /// Makes a move in the string form, e.g. "Attack:Head Protect:Legs", with an optional ability and consumable
+    pub fn make_action(&mut self, battle_id: BattleId, params: String, ability: Option<Ability>, consumable: Option<Consumable>) {
+        self.play_round(battle_id, |warrior| {
+            parse_move(&params, warrior.get_attack_slots(), warrior.get_protect_slots())
+                .map(|warrior_move| WarriorMove { ability, consumable, ..warrior_move })
+        });
+    }
+
+    /// Makes a move in the JSON form, see `MoveInput`
+    pub fn make_move(&mut self, battle_id: BattleId, warrior_move: MoveInput) {
+        self.play_round(battle_id, |warrior| warrior_move.to_warrior_move(warrior.get_attack_slots(), warrior.get_protect_slots()));
+    }
+}
+
+impl DeFight {
+    fn play_round(&mut self, battle_id: BattleId, read_move: impl FnOnce(&Warrior) -> Result<WarriorMove, InputError>) {
+        let account_id = env::predecessor_account_id();
         let mut battle: Battle = self.get_battle(&battle_id).into();
-        
+
+        assert_eq!(battle.warrior_1.account_id.as_ref(), Some(&account_id), "Only the warrior of the battle can make moves");
         assert!(battle.winner.is_none(), "Battle has already finished");
 
-        // const account_id: String = env::predecessor_account_id();
+        // The player pays for the whole legacy battle when it's moved to the versioned battles
+        if self.is_legacy_battle(&battle_id) {
+            self.remove_saved_battle(&battle_id);
+        }
+        let initial_storage_usage = env::storage_usage();
 
         let log_message = format!("Battle state: {:?}", battle.winner.is_none());
         env::log(log_message.as_bytes());
 
-        let parse_result = parse_move(&params);
+        let parse_result = read_move(&battle.warrior_1);
 
         match parse_result {
-            Ok(actions) => {
-                let log_message = format!("Actions: {:?}", actions);
+            Ok(warrior_move) => {
+                if let Some(ability) = warrior_move.ability {
+                    if let Err(e) = battle.warrior_1_abilities.check(&battle.warrior_1, ability, battle.round) {
+                        panic!("\n *** {}", e);
+                    }
+                }
+
+                if let Some(consumable) = warrior_move.consumable {
+                    self.take_consumable(&account_id, consumable);
+                    battle.apply_consumable(consumable);
+                }
+
+                let log_message = format!("Move: {:?}", warrior_move);
                 env::log(log_message.as_bytes());
 
-                let result = battle.apply_actions(actions);
+                let result = battle.apply_actions(warrior_move);
 
                 let log_message = format!("Result: {:?}", result);
                 env::log(log_message.as_bytes());   
-                self.battles.insert(&battle_id, &result);
+                self.save_battle(&battle_id, &result);
 
                 if result.winner == Some(0) {
-                    let log_message = format!("Battle is over! Draw");
-                    env::log(log_message.as_bytes());  
+                    env::log(b"Battle is over! Draw");
                 }
 
                 if result.winner == Some(1) {
                     let log_message = format!("Battle is over! Winner: {:?}", result.warrior_1.account_id);
                     env::log(log_message.as_bytes());  
+
+                    if let Some(account_id) = &result.warrior_1.account_id {
+                        self.roll_loot(account_id);
+                    }
                 }
 
+                self.add_battle_experience(&result);
+
                 if result.winner == Some(2) {
                     let log_message = format!("Battle is over! Winner: {:?}", result.warrior_2.account_id);
                     env::log(log_message.as_bytes());  
                 }
+
+                // The player pays for the battle state, the loot and the progress
+                self.charge_storage(&account_id, initial_storage_usage);
             },
-            Err(e) => match e {
-                InputError::WrongActions { actions: errors } => {
-                    for error in errors {
-                        match error {
-                            ParseError::WrongAction { action } =>
-                                panic!("\n *** Action {} doesn't exist in the game", action),
-                            ParseError::WrongPart { part } =>
-                                panic!("\n *** Part '{}' doesn't exist in the game", part),
-                        }
-                    }
-                }
-                InputError::TooFewActions =>
-                    panic!("\n *** You must specify two actions - Attack and Protect"),
-            }
+            Err(e) => panic!("\n *** {}", e),
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-}
\ No newline at end of file
+    use super::*;
+    use crate::test_utils::*;
+    use near_sdk::test_utils::accounts;
+    use near_sdk::json_types::U64;
+    use near_sdk::{testing_env, MockedBlockchain};
+
+    #[test]
+    fn test_seed_indexes_fit_random_seed() {
+        testing_env!(get_context(accounts(0)).build());
+        assert!(LOOT_SEED_INDEX + 3 * 4 <= env::random_seed().len());
+    }
+
+    #[test]
+    fn test_equip_and_unequip() {
+        let mut contract = setup_contract();
+        testing_env!(get_context(accounts(1)).build());
+
+        contract.equip(Place::Helmet, PARAS_CONTRACT_ID.to_string(), "1:1".to_string());
+        contract.equip(Place::Weapon2, PARAS_CONTRACT_ID.to_string(), "2:5".to_string());
+        contract.unequip(Place::Helmet);
+
+        let equipment = contract.get_equipment(&accounts(1).into()).unwrap();
+        assert_eq!(equipment.helmet, None);
+        assert_eq!(equipment.weapon_2, Some(paras_token("2:5")));
+    }
+
+    #[test]
+    #[should_panic(expected = "Token can't be equipped as Armor")]
+    fn test_equip_wrong_place() {
+        let mut contract = setup_contract();
+        testing_env!(get_context(accounts(1)).build());
+
+        contract.equip(Place::Armor, PARAS_CONTRACT_ID.to_string(), "1:1".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Token is already equipped in another place")]
+    fn test_equip_same_token_twice() {
+        let mut contract = setup_contract();
+        testing_env!(get_context(accounts(1)).build());
+
+        contract.equip(Place::Weapon1, PARAS_CONTRACT_ID.to_string(), "2:5".to_string());
+        contract.equip(Place::Weapon2, PARAS_CONTRACT_ID.to_string(), "2:5".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Token can't be equipped as Armor")]
+    fn test_change_equipment_wrong_place() {
+        let mut contract = setup_contract();
+        testing_env!(get_context(accounts(1)).build());
+
+        let equipment = EquipmentConfig { armor: Some(paras_token("1:1")), ..Default::default() };
+        contract.change_warrior_equipment(equipment);
+    }
+
+    #[test]
+    #[should_panic(expected = "Token is already equipped in another place")]
+    fn test_change_equipment_same_token_twice() {
+        let mut contract = setup_contract();
+        testing_env!(get_context(accounts(1)).build());
+
+        let equipment = EquipmentConfig {
+            weapon_1: Some(paras_token("2:5")),
+            weapon_2: Some(paras_token("2:5")),
+            ..Default::default()
+        };
+        contract.change_warrior_equipment(equipment);
+    }
+
+    #[test]
+    fn test_equip_shield() {
+        let mut contract = setup_contract();
+        testing_env!(get_context(accounts(1)).build());
+
+        contract.equip(Place::Weapon1, PARAS_CONTRACT_ID.to_string(), "2:5".to_string());
+        contract.equip(Place::Weapon2, PARAS_CONTRACT_ID.to_string(), "4:1".to_string());
+        assert_eq!(contract.get_warrior(accounts(1).into()).protect_slots, 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "Two-handed weapon occupies both weapon slots")]
+    fn test_equip_two_handed_with_shield() {
+        let mut contract = setup_contract();
+        testing_env!(get_context(accounts(1)).build());
+
+        contract.equip(Place::Weapon2, PARAS_CONTRACT_ID.to_string(), "4:1".to_string());
+        contract.equip(Place::Weapon1, PARAS_CONTRACT_ID.to_string(), "3:1".to_string());
+    }
+
+    #[test]
+    fn test_item_set_bonus() {
+        let mut contract = setup_contract();
+        let piece = |token_series_id: &str| ItemSetPiece {
+            contract_id: PARAS_CONTRACT_ID.to_string(),
+            token_series_id: token_series_id.to_string(),
+        };
+        contract.set_item_set("warlord".to_string(), ItemSet {
+            pieces: vec![piece("1"), piece("2")],
+            bonuses: vec![SetBonus { pieces: 2, strength: 4, stamina: 0, agility: 0, intuition: 0, abilities: vec![Ability::Heal] }],
+        });
+        testing_env!(get_context(accounts(1)).build());
+
+        contract.equip(Place::Helmet, PARAS_CONTRACT_ID.to_string(), "1:1".to_string());
+        assert_eq!(contract.get_warrior(accounts(1).into()).strength, BASE_STRENGTH + 2);
+
+        contract.equip(Place::Weapon1, PARAS_CONTRACT_ID.to_string(), "2:5".to_string());
+        let warrior = contract.get_warrior(accounts(1).into());
+        assert_eq!(warrior.strength, BASE_STRENGTH + 2 + 3 + 4);
+        assert!(warrior.abilities.contains(&Ability::Heal));
+
+        testing_env!(get_context(accounts(0)).build());
+        let bonuses = contract.item_sets.get(&"warlord".to_string()).unwrap().bonuses;
+        contract.set_item_set("warlord".to_string(), ItemSet { pieces: vec![piece("3"), piece("4")], bonuses });
+        assert_eq!(contract.get_warrior(accounts(1).into()).strength, BASE_STRENGTH + 2 + 3);
+        assert!(contract.item_sets_by_piece.get(&piece("1")).is_none());
+
+        contract.remove_item_set("warlord".to_string());
+        assert!(contract.item_sets_by_piece.get(&piece("3")).is_none());
+    }
+
+    #[test]
+    fn test_warrior_loadout_cache() {
+        let mut contract = setup_contract();
+        testing_env!(get_context(accounts(1)).build());
+
+        contract.equip(Place::Helmet, PARAS_CONTRACT_ID.to_string(), "1:1".to_string());
+        assert_eq!(contract.get_warrior(accounts(1).into()).strength, BASE_STRENGTH + 2);
+
+        testing_env!(get_context(accounts(0)).build());
+        contract.remove_token_series(PARAS_CONTRACT_ID.to_string(), "1".to_string());
+        assert_eq!(contract.get_warrior(accounts(1).into()).strength, BASE_STRENGTH);
+    }
+
+    #[test]
+    fn test_confirm_ownership_unequips_transferred_tokens() {
+        let mut contract = setup_contract();
+        testing_env!(get_context(accounts(1)).build());
+
+        contract.equip(Place::Helmet, PARAS_CONTRACT_ID.to_string(), "1:1".to_string());
+        contract.equip(Place::Weapon1, PARAS_CONTRACT_ID.to_string(), "2:5".to_string());
+
+        let warrior = contract.confirm_ownership(&accounts(1).into(), &[paras_token("1:1")]);
+
+        assert_eq!(warrior.strength, BASE_STRENGTH + 3);
+        assert_eq!(contract.get_equipment(&accounts(1).into()).unwrap().helmet, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the warrior of the battle can make moves")]
+    fn test_make_move_in_battle_of_another_account() {
+        let mut contract = setup_contract();
+        let battle = BattleToSave::new(Warrior::new(1, Some(accounts(1).into())), &Profile::default(), Warrior::new(2, None), None, BattleFormat::Elimination);
+        contract.save_battle(&0, &battle);
+
+        testing_env!(get_context(accounts(2)).build());
+        contract.make_action(0, "Attack:Head Protect:Legs".to_string(), None, None);
+    }
+
+    #[test]
+    fn test_make_move_charges_storage() {
+        let mut contract = setup_contract();
+        let battle = BattleToSave::new(Warrior::new(1, Some(accounts(1).into())), &Profile::default(), Warrior::new(2, None), None, BattleFormat::Elimination);
+        contract.save_battle(&0, &battle);
+
+        testing_env!(get_context(accounts(1)).build());
+        let initial_storage_usage = env::storage_usage();
+        contract.make_action(0, "Attack:Head Protect:Legs".to_string(), None, None);
+
+        let used_bytes = contract.storage_accounts.get(&accounts(1).into()).unwrap().used_bytes;
+        assert_eq!(used_bytes, env::storage_usage() - initial_storage_usage);
+        assert!(used_bytes > 0);
+    }
+
+    #[test]
+    fn test_resolve_owner_tokens_keeps_unverified_tokens() {
+        let mut contract = setup_contract();
+        testing_env!(get_context(accounts(1)).build());
+
+        contract.equip(Place::Helmet, PARAS_CONTRACT_ID.to_string(), "1:1".to_string());
+        contract.equip(Place::Weapon1, PARAS_CONTRACT_ID.to_string(), "2:5".to_string());
+        let tokens = contract.get_equipment(&accounts(1).into()).unwrap().external_tokens();
+
+        let transferred_token = Token {
+            token_id: "2:5".to_string(),
+            owner_id: accounts(2).into(),
+            metadata: None,
+            approved_account_ids: None,
+        };
+        testing_env!(
+            get_context(accounts(0)).build(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            vec![
+                PromiseResult::Failed,
+                PromiseResult::Successful(near_sdk::serde_json::to_vec(&Some(transferred_token)).unwrap()),
+            ]
+        );
+        contract.resolve_owner_tokens(accounts(1).into(), None, BattleSettings { bot_difficulty: BotDifficulty::Easy, format: BattleFormat::Elimination }, tokens);
+
+        let equipment = contract.get_equipment(&accounts(1).into()).unwrap();
+        assert_eq!(equipment.helmet, Some(paras_token("1:1")));
+        assert_eq!(equipment.weapon_1, None);
+    }
+
+    #[test]
+    fn test_bot_does_not_get_player_equipment() {
+        let mut contract = setup_contract();
+        testing_env!(get_context(accounts(1)).build());
+
+        contract.equip(Place::Weapon1, PARAS_CONTRACT_ID.to_string(), "2:5".to_string());
+        let warrior = contract.confirm_ownership(&accounts(1).into(), &[]);
+        let bot = DeFight::build_bot(&accounts(1).into(), BotDifficulty::Easy);
+        let battle = BattleToSave::new(warrior, &Profile::default(), bot, None, BattleFormat::Elimination);
+
+        assert_eq!(battle.warrior_1.strength, BASE_STRENGTH + 3);
+        let bot_points = battle.warrior_2.strength + battle.warrior_2.stamina + battle.warrior_2.agility + battle.warrior_2.intuition;
+        assert_eq!(bot_points, BASE_STRENGTH + BASE_STAMINA + BASE_AGILITY + BASE_INTUITION + 2);
+        assert_eq!(battle.warrior_2.id, 2);
+    }
+
+    #[test]
+    fn test_equip_nep171_token() {
+        let mut contract = setup_contract();
+        contract.add_nft_contract("items.near".to_string(), NftContractKind::Nep171);
+        let token_series = TokenSeries {
+            contract_id: "items.near".to_string(),
+            token_series_id: "sword:1".to_string(),
+            ..token_series("", r#"{"place": "weapon", "agility": 4}"#)
+        };
+        contract.tokens_series.insert(&("items.near".to_string(), "sword:1".to_string()), &ETokenSeries::Current(token_series));
+
+        testing_env!(get_context(accounts(1)).build());
+        contract.equip(Place::Weapon1, "items.near".to_string(), "sword:1".to_string());
+        contract.equip(Place::Helmet, PARAS_CONTRACT_ID.to_string(), "1:1".to_string());
+
+        assert_eq!(contract.get_warrior(accounts(1).into()).agility, BASE_AGILITY + 4);
+        assert_eq!(
+            contract.get_equipment(&accounts(1).into()).unwrap().external_tokens(),
+            vec![paras_token("1:1"), EquippedToken { contract_id: "items.near".to_string(), token_id: "sword:1".to_string() }]
+        );
+    }
+
+    #[test]
+    fn test_get_token_series_page_past_the_end() {
+        let contract = setup_contract();
+
+        let token_series = contract.get_token_series(U64(3), U64(u64::MAX));
+        assert_eq!(token_series.len(), 1);
+        assert_eq!(token_series[0].token_series_id, "4".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Token series is not allowed in the game")]
+    fn test_equip_unknown_contract() {
+        let mut contract = setup_contract();
+        testing_env!(get_context(accounts(1)).build());
+
+        contract.equip(Place::Helmet, "items.near".to_string(), "1:1".to_string());
+    }
+
+    #[test]
+    fn test_equip_native_item() {
+        let mut contract = setup_contract();
+        testing_env!(get_context(accounts(0)).attached_deposit(10u128.pow(24)).build());
+        let attributes = ItemAttributes::parse(r#"{"place": "armor", "stamina": 2}"#).unwrap();
+        let token = contract.nft_mint_item(accounts(1), token_metadata(), attributes.clone());
+        assert_eq!(ItemAttributes::parse(&token.metadata.unwrap().extra.unwrap()), Ok(attributes));
+
+        testing_env!(get_context(accounts(1)).build());
+        contract.equip(Place::Armor, accounts(0).into(), token.token_id.clone());
+        assert_eq!(contract.get_warrior(accounts(1).into()).health, BASE_HEALTH + 20);
+
+        contract.tokens.internal_transfer_unguarded(&token.token_id, &accounts(1).into(), &accounts(2).into());
+        let warrior = contract.confirm_ownership(&accounts(1).into(), &[]);
+        assert_eq!(warrior.health, BASE_HEALTH);
+    }
+
+    #[test]
+    #[should_panic(expected = "Token is not owned by the account")]
+    fn test_equip_native_item_of_another_account() {
+        let mut contract = setup_contract();
+        testing_env!(get_context(accounts(0)).attached_deposit(10u128.pow(24)).build());
+        let attributes = ItemAttributes::parse(r#"{"place": "armor", "stamina": 2}"#).unwrap();
+        let token = contract.nft_mint_item(accounts(2), token_metadata(), attributes);
+
+        testing_env!(get_context(accounts(1)).build());
+        contract.equip(Place::Armor, accounts(0).into(), token.token_id);
+    }
+}
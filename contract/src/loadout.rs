@@ -0,0 +1,156 @@
+use crate::*;
+use crate::migration::{LoadoutV0, WarriorV1, WarriorV2, WarriorV3, WarriorV4, WarriorV5};
+use near_sdk::env::random_seed;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, PartialEq, Debug, Copy, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub enum BotDifficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl Default for BotDifficulty {
+    fn default() -> Self {
+        BotDifficulty::Normal
+    }
+}
+
+impl BotDifficulty {
+    /// Stat points randomly spread over the base bot stats
+    fn stat_points(&self) -> u16 {
+        match self {
+            BotDifficulty::Easy => 2,
+            BotDifficulty::Normal => 6,
+            BotDifficulty::Hard => 12,
+        }
+    }
+}
+
+/// Warrior stat block built from the account equipment.
+/// Cached per account and rebuilt when the equipment or the token series change.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Loadout {
+    pub warrior: Warrior,
+    pub tokens_series_version: u64,
+}
+
+/// Loadouts of old layouts are stale and rebuilt
+#[derive(BorshDeserialize, BorshSerialize)]
+pub enum ELoadout {
+    V0(LoadoutV0),
+    V1(LoadoutV0<WarriorV1>),
+    V2(LoadoutV0<WarriorV2>),
+    V3(LoadoutV0<WarriorV3>),
+    V4(LoadoutV0<WarriorV4>),
+    V5(LoadoutV0<WarriorV5>),
+    Current(Loadout),
+}
+
+#[near_bindgen]
+impl DeFight {
+    pub(crate) fn build_warrior(&self, account_id: &AccountId, equipment: &EquipmentConfig) -> Warrior {
+        let mut warrior = Warrior::new(1, Some(account_id.clone()));
+        warrior.apply_level(self.get_account_level(account_id));
+        let mut equipped_tokens = vec![];
+
+        let ignored_places = self.get_ignored_weapon_places(equipment);
+
+        for place in Place::ALL.iter().filter(|place| !ignored_places.contains(place)) {
+            if let Some(token) = equipment.slot(*place) {
+                if let Some(attributes) = self.get_item(token) {
+                    if place.accepts(attributes.place) {
+                        warrior.apply_item(&attributes);
+                        if let Some(category) = attributes.get_weapon_category() {
+                            warrior.apply_weapon_category(*place, category);
+                        }
+                        equipped_tokens.push(token.clone());
+                    }
+                }
+            }
+        }
+
+        self.apply_set_bonuses(&mut warrior, &equipped_tokens);
+
+        warrior
+    }
+
+    /// Generates the bot stat block. It doesn't depend on the player equipment.
+    pub(crate) fn build_bot(account_id: &AccountId, difficulty: BotDifficulty) -> Warrior {
+        let mut bot = Warrior::new(2, Some(account_id.clone() + " (bot)"));
+        let seed = random_seed();
+
+        for point in 0..difficulty.stat_points() {
+            match seed[point as usize % seed.len()] % 4 {
+                0 => bot.add_stats(1, 0, 0, 0),
+                1 => bot.add_stats(0, 1, 0, 0),
+                2 => bot.add_stats(0, 0, 1, 0),
+                _ => bot.add_stats(0, 0, 0, 1),
+            }
+        }
+
+        bot
+    }
+
+    pub(crate) fn refresh_loadout(&mut self, account_id: &AccountId) -> Warrior {
+        let equipment = self.get_equipment(account_id).unwrap_or_default();
+        let warrior = self.build_warrior(account_id, &equipment);
+
+        self.save_loadout(account_id, Loadout {
+            warrior: warrior.clone(),
+            tokens_series_version: self.tokens_series_version,
+        });
+
+        warrior
+    }
+
+    /// Unequips `not_owned_tokens` and native tokens which are no longer owned by the account, returns the actual warrior
+    pub(crate) fn confirm_ownership(&mut self, account_id: &AccountId, not_owned_tokens: &[EquippedToken]) -> Warrior {
+        if let Some(mut equipment) = self.get_equipment(account_id) {
+            let mut is_changed = false;
+
+            for place in Place::ALL.iter() {
+                let slot = equipment.slot_mut(*place);
+                let is_owned = slot.as_ref()
+                    .map_or(true, |token| if DeFight::is_native_token(token) {
+                        self.is_native_token_owner(account_id, &token.token_id)
+                    } else {
+                        !not_owned_tokens.contains(token)
+                    });
+
+                if !is_owned {
+                    emit_event("unequip", EquipmentEventData {
+                        account_id: account_id.clone(),
+                        place: *place,
+                        token: slot.take(),
+                    });
+                    is_changed = true;
+                }
+            }
+
+            if is_changed {
+                self.save_equipment(account_id, &equipment);
+                return self.refresh_loadout(account_id);
+            }
+        }
+
+        match self.get_loadout(account_id) {
+            Some(loadout) if loadout.tokens_series_version == self.tokens_series_version => loadout.warrior,
+            _ => self.refresh_loadout(account_id),
+        }
+    }
+
+    /// Warrior stats with the allocated stat points as they will be at the start of a battle
+    pub fn get_warrior(&self, account_id: AccountId) -> Warrior {
+        let mut warrior = match self.get_loadout(&account_id) {
+            Some(loadout) if loadout.tokens_series_version == self.tokens_series_version => loadout.warrior,
+            _ => {
+                let equipment = self.get_equipment(&account_id).unwrap_or_default();
+                self.build_warrior(&account_id, &equipment)
+            }
+        };
+        warrior.apply_profile(&self.get_profile(&account_id));
+
+        warrior
+    }
+}
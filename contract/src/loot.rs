@@ -0,0 +1,244 @@
+use crate::*;
+use crate::migration::{ItemAttributesV1, ItemAttributesV2, ItemAttributesV3, ItemAttributesV4, ItemAttributesV5, LootConfigV0};
+use near_sdk::env::random_seed;
+
+const NS_IN_DAY: u64 = 86_400_000_000_000;
+/// Drop chance is set in basis points
+const MAX_DROP_CHANCE: u16 = 10_000;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct LootItem {
+    pub metadata: TokenMetadata,
+    pub attributes: ItemAttributes,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RarityTier {
+    pub name: String,
+    /// Relative chance of the tier among all tiers
+    pub weight: u32,
+    pub items: Vec<LootItem>,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct LootConfig {
+    /// Chance to get any drop after a won battle, in basis points
+    pub drop_chance: u16,
+    /// Max drops per account in a day
+    pub daily_cap: u32,
+    pub tiers: Vec<RarityTier>,
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub enum ELootConfig {
+    V0(LootConfigV0),
+    V1(LootConfigV0<ItemAttributesV1>),
+    V2(LootConfigV0<ItemAttributesV2>),
+    V3(LootConfigV0<ItemAttributesV3>),
+    V4(LootConfigV0<ItemAttributesV4>),
+    V5(LootConfigV0<ItemAttributesV5>),
+    Current(LootConfig),
+}
+
+impl From<ELootConfig> for LootConfig {
+    fn from(e_loot_config: ELootConfig) -> Self {
+        match e_loot_config {
+            ELootConfig::V0(loot_config) => loot_config.into(),
+            ELootConfig::V1(loot_config) => loot_config.into(),
+            ELootConfig::V2(loot_config) => loot_config.into(),
+            ELootConfig::V3(loot_config) => loot_config.into(),
+            ELootConfig::V4(loot_config) => loot_config.into(),
+            ELootConfig::V5(loot_config) => loot_config.into(),
+            ELootConfig::Current(loot_config) => loot_config,
+        }
+    }
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct LootDrops {
+    pub day: u64,
+    pub drops_num: u32,
+}
+
+impl LootConfig {
+    fn assert_valid(&self) {
+        assert!(self.drop_chance <= MAX_DROP_CHANCE, "Drop chance can't be greater than {}", MAX_DROP_CHANCE);
+
+        let mut total_weight: u32 = 0;
+        for tier in self.tiers.iter() {
+            assert!(tier.weight > 0, "Tier '{}' has zero weight", tier.name);
+            total_weight = total_weight.checked_add(tier.weight).expect("Total weight of the tiers is too big");
+            assert!(!tier.items.is_empty(), "Tier '{}' has no items", tier.name);
+
+            for item in tier.items.iter() {
+                if let Err(e) = item.attributes.validate() {
+                    env::panic(format!("Wrong item attributes in tier '{}': {}", tier.name, e).as_bytes());
+                }
+            }
+        }
+    }
+
+    /// Picks the tier by weight and an item of the tier with the given random numbers
+    fn roll(&self, drop_roll: u32, tier_roll: u32, item_roll: u32) -> Option<(&RarityTier, &LootItem)> {
+        let total_weight: u32 = self.tiers.iter().map(|tier| tier.weight).sum();
+
+        if total_weight == 0 || drop_roll % MAX_DROP_CHANCE as u32 >= self.drop_chance as u32 {
+            return None;
+        }
+
+        let mut tier_roll = tier_roll % total_weight;
+        let tier = self.tiers.iter().find(|tier| {
+            if tier_roll < tier.weight {
+                true
+            } else {
+                tier_roll -= tier.weight;
+                false
+            }
+        })?;
+
+        let item = &tier.items[item_roll as usize % tier.items.len()];
+
+        Some((tier, item))
+    }
+}
+
+fn random_u32(seed: &[u8], offset: usize) -> u32 {
+    let mut bytes = [0u8; 4];
+    for (index, byte) in bytes.iter_mut().enumerate() {
+        *byte = seed[(offset + index) % seed.len()];
+    }
+
+    u32::from_le_bytes(bytes)
+}
+
+#[near_bindgen]
+impl DeFight {
+    pub fn set_loot_config(&mut self, loot_config: LootConfig) {
+        self.assert_owner();
+        loot_config.assert_valid();
+
+        self.loot_config.set(&ELootConfig::Current(loot_config));
+    }
+
+    pub fn get_loot_config(&self) -> Option<LootConfig> {
+        self.loot_config.get().map(LootConfig::from)
+    }
+
+    /// Rolls the loot table after a won battle and mints the dropped item to the winner
+    pub(crate) fn roll_loot(&mut self, account_id: &AccountId) -> Option<Token> {
+        let loot_config = self.get_loot_config()?;
+
+        let day = env::block_timestamp() / NS_IN_DAY;
+        let mut loot_drops = match self.loot_drops.get(account_id) {
+            Some(loot_drops) if loot_drops.day == day => loot_drops,
+            _ => LootDrops { day, drops_num: 0 },
+        };
+
+        if loot_drops.drops_num >= loot_config.daily_cap {
+            return None;
+        }
+
+        let seed = random_seed();
+        let (tier, item) = loot_config.roll(
+            random_u32(&seed, LOOT_SEED_INDEX),
+            random_u32(&seed, LOOT_SEED_INDEX + 4),
+            random_u32(&seed, LOOT_SEED_INDEX + 8),
+        )?;
+
+        let token = self.internal_mint_item(account_id, item.metadata.clone(), item.attributes.clone());
+
+        loot_drops.drops_num += 1;
+        self.loot_drops.insert(account_id, &loot_drops);
+
+        emit_event("loot_drop", LootDropEventData {
+            account_id: account_id.clone(),
+            tier: tier.name.clone(),
+            token_id: token.token_id.clone(),
+        });
+
+        Some(token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::{testing_env, MockedBlockchain};
+
+    fn loot_item(place: Place) -> LootItem {
+        LootItem {
+            metadata: token_metadata(),
+            attributes: ItemAttributes { strength: 1, ..ItemAttributes::new(place) },
+        }
+    }
+
+    #[test]
+    fn test_loot_roll() {
+        let loot_config = LootConfig {
+            drop_chance: 5_000,
+            daily_cap: 1,
+            tiers: vec![
+                RarityTier { name: "common".to_string(), weight: 3, items: vec![loot_item(Place::Helmet), loot_item(Place::Boots)] },
+                RarityTier { name: "rare".to_string(), weight: 1, items: vec![loot_item(Place::Weapon1)] },
+            ],
+        };
+
+        assert!(loot_config.roll(5_000, 0, 0).is_none());
+
+        let (tier, item) = loot_config.roll(4_999, 2, 1).unwrap();
+        assert_eq!((tier.name.as_str(), item.attributes.place), ("common", Place::Boots));
+
+        let (tier, item) = loot_config.roll(0, 3, 1).unwrap();
+        assert_eq!((tier.name.as_str(), item.attributes.place), ("rare", Place::Weapon1));
+    }
+
+    #[test]
+    #[should_panic(expected = "Total weight of the tiers is too big")]
+    fn test_loot_total_weight_overflow() {
+        let tier = RarityTier { name: "common".to_string(), weight: u32::MAX, items: vec![loot_item(Place::Amulet)] };
+        LootConfig { drop_chance: MAX_DROP_CHANCE, daily_cap: 1, tiers: vec![tier.clone(), tier] }.assert_valid();
+    }
+
+    #[test]
+    fn test_loot_storage_is_charged_to_winner() {
+        let mut contract = setup_contract();
+        contract.set_loot_config(LootConfig {
+            drop_chance: MAX_DROP_CHANCE,
+            daily_cap: 1,
+            tiers: vec![RarityTier { name: "common".to_string(), weight: 1, items: vec![loot_item(Place::Amulet)] }],
+        });
+        let mut bot = Warrior::new(2, None);
+        bot.health = 1;
+        contract.save_battle(&0, &BattleToSave::new(Warrior::new(1, Some(accounts(1).into())), &Profile::default(), bot, None, BattleFormat::Elimination));
+
+        testing_env!(get_context(accounts(1)).build());
+        let initial_storage_usage = env::storage_usage();
+        contract.make_action(0, "Attack:Legs Protect:Head".to_string(), None, None);
+
+        assert_eq!(contract.loot_drops.get(&accounts(1).into()).unwrap().drops_num, 1);
+        let used_bytes = contract.storage_accounts.get(&accounts(1).into()).unwrap().used_bytes;
+        assert_eq!(used_bytes, env::storage_usage() - initial_storage_usage);
+    }
+
+    #[test]
+    fn test_loot_daily_cap() {
+        let mut contract = setup_contract();
+        contract.set_loot_config(LootConfig {
+            drop_chance: MAX_DROP_CHANCE,
+            daily_cap: 1,
+            tiers: vec![RarityTier { name: "common".to_string(), weight: 1, items: vec![loot_item(Place::Amulet)] }],
+        });
+
+        let token = contract.roll_loot(&accounts(1).into()).unwrap();
+        assert_eq!(contract.get_item_attributes(token.token_id).unwrap().place, Place::Amulet);
+        assert!(contract.roll_loot(&accounts(1).into()).is_none());
+
+        testing_env!(VMContextBuilder::new().block_timestamp(NS_IN_DAY).build());
+        assert!(contract.roll_loot(&accounts(1).into()).is_some());
+    }
+}
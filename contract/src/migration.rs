@@ -0,0 +1,996 @@
+use crate::*;
+
+/// Kept outside of the contract struct, so `migrate` knows the layout of the old state before reading it
+const STATE_VERSION_KEY: &[u8] = b"STATE_VERSION";
+/// Version of the `DeFight` layout, the first contract version has no key and is `0`
+pub(crate) const STATE_VERSION: u32 = 10;
+
+/// State of the first contract version
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct DeFightV0 {
+    pub(crate) owner_ids: UnorderedSet<AccountId>,
+    pub(crate) tokens_series: UnorderedMap<TokenId, TokenSeriesJson>,
+    pub(crate) battles: LookupMap<BattleId, BattleToSaveV0>,
+    pub(crate) available_warriors: UnorderedMap<AccountId, EBattleConfig>,
+    pub(crate) warriors_equipment: LookupMap<AccountId, EquipmentConfigV0>,
+    pub(crate) stats: UnorderedMap<AccountId, EStats>,
+    pub(crate) available_battles: UnorderedMap<BattleId, (AccountId, AccountId)>,
+    pub(crate) next_battle_id: BattleId,
+    pub(crate) service_fee: Balance,
+}
+
+/// Warrior of the first version, it had only the basic stats
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct WarriorV0 {
+    pub(crate) id: u32,
+    pub(crate) account_id: Option<AccountId>,
+    pub(crate) strength: u16,
+    pub(crate) stamina: u16,
+    pub(crate) agility: u16,
+    pub(crate) intuition: u16,
+    pub(crate) health: u16,
+    pub(crate) defense: u16,
+}
+
+impl From<WarriorV0> for Warrior {
+    fn from(warrior: WarriorV0) -> Self {
+        Warrior {
+            strength: warrior.strength,
+            stamina: warrior.stamina,
+            agility: warrior.agility,
+            intuition: warrior.intuition,
+            health: warrior.health,
+            defense: warrior.defense,
+            ..Warrior::new(warrior.id, warrior.account_id)
+        }
+    }
+}
+
+/// Battle of the first version, a single duel. The warrior is generic so later warrior layouts reuse it.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct BattleToSaveV0<T = WarriorV0> {
+    pub(crate) warrior_1: T,
+    pub(crate) warrior_2: T,
+    pub(crate) winner: Option<u32>,
+    pub(crate) reward: Balance,
+    pub(crate) last_action_timestamp: Timestamp,
+    pub(crate) warrior_1_missed_action: bool,
+    pub(crate) warrior_2_missed_action: bool,
+    pub(crate) warrior_1_last_attack: Option<String>,
+    pub(crate) warrior_1_last_protect: Option<String>,
+    pub(crate) warrior_2_last_attack: Option<String>,
+    pub(crate) warrior_2_last_protect: Option<String>,
+}
+
+impl<T: Into<Warrior>> From<BattleToSaveV0<T>> for BattleToSave {
+    fn from(battle: BattleToSaveV0<T>) -> Self {
+        let warrior_1: Warrior = battle.warrior_1.into();
+        let warrior_2: Warrior = battle.warrior_2.into();
+
+        BattleToSave {
+            // Max health wasn't saved, the current health is the best guess
+            warrior_1_max_health: warrior_1.health,
+            warrior_2_max_health: warrior_2.health,
+            warrior_1_abilities: AbilityState::new(&warrior_1),
+            warrior_2_abilities: AbilityState::new(&warrior_2),
+            warrior_1_effects: vec![],
+            warrior_2_effects: vec![],
+            warrior_1,
+            warrior_2,
+            winner: battle.winner,
+            reward: battle.reward,
+            last_action_timestamp: battle.last_action_timestamp,
+            warrior_1_missed_action: battle.warrior_1_missed_action,
+            warrior_2_missed_action: battle.warrior_2_missed_action,
+            warrior_1_last_attack: battle.warrior_1_last_attack,
+            warrior_1_last_protect: battle.warrior_1_last_protect,
+            warrior_2_last_attack: battle.warrior_2_last_attack,
+            warrior_2_last_protect: battle.warrior_2_last_protect,
+            format: BattleFormat::Elimination,
+            round: 0,
+            duel: 0,
+            warrior_1_duel_wins: 0,
+            warrior_2_duel_wins: 0,
+        }
+    }
+}
+
+/// Warrior with the attack and protect slots
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct WarriorV1 {
+    pub(crate) v0: WarriorV0,
+    pub(crate) attack_slots: u8,
+    pub(crate) protect_slots: u8,
+}
+
+impl From<WarriorV1> for Warrior {
+    fn from(warrior: WarriorV1) -> Self {
+        Warrior {
+            attack_slots: warrior.attack_slots,
+            protect_slots: warrior.protect_slots,
+            ..warrior.v0.into()
+        }
+    }
+}
+
+/// Warrior with the armor of every body part
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct WarriorV2 {
+    pub(crate) v1: WarriorV1,
+    pub(crate) armor: [u16; 5],
+}
+
+impl From<WarriorV2> for Warrior {
+    fn from(warrior: WarriorV2) -> Self {
+        Warrior {
+            armor: warrior.armor,
+            ..warrior.v1.into()
+        }
+    }
+}
+
+/// Warrior with the abilities
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct WarriorV3 {
+    pub(crate) v2: WarriorV2,
+    pub(crate) abilities: Vec<Ability>,
+}
+
+impl From<WarriorV3> for Warrior {
+    fn from(warrior: WarriorV3) -> Self {
+        Warrior {
+            abilities: warrior.abilities,
+            ..warrior.v2.into()
+        }
+    }
+}
+
+/// Warrior with the status effects put on a hit
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct WarriorV4 {
+    pub(crate) v3: WarriorV3,
+    pub(crate) hit_effects: Vec<StatusEffect>,
+}
+
+impl From<WarriorV4> for Warrior {
+    fn from(warrior: WarriorV4) -> Self {
+        Warrior {
+            hit_effects: warrior.hit_effects,
+            ..warrior.v3.into()
+        }
+    }
+}
+
+/// Warrior with the damage types and resistances
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct WarriorV5 {
+    pub(crate) v4: WarriorV4,
+    pub(crate) damage_types: Vec<DamageType>,
+    pub(crate) resistances: Vec<Resistance>,
+}
+
+impl From<WarriorV5> for Warrior {
+    fn from(warrior: WarriorV5) -> Self {
+        Warrior {
+            damage_types: warrior.damage_types,
+            resistances: warrior.resistances,
+            ..warrior.v4.into()
+        }
+    }
+}
+
+/// Battle with the format and the max health of the warriors
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct BattleToSaveV1<T = WarriorV0> {
+    pub(crate) v0: BattleToSaveV0<T>,
+    pub(crate) format: BattleFormat,
+    pub(crate) round: u16,
+    pub(crate) duel: u8,
+    pub(crate) warrior_1_duel_wins: u8,
+    pub(crate) warrior_2_duel_wins: u8,
+    pub(crate) warrior_1_max_health: u16,
+    pub(crate) warrior_2_max_health: u16,
+}
+
+impl<T: Into<Warrior>> From<BattleToSaveV1<T>> for BattleToSave {
+    fn from(battle: BattleToSaveV1<T>) -> Self {
+        BattleToSave {
+            format: battle.format,
+            round: battle.round,
+            duel: battle.duel,
+            warrior_1_duel_wins: battle.warrior_1_duel_wins,
+            warrior_2_duel_wins: battle.warrior_2_duel_wins,
+            warrior_1_max_health: battle.warrior_1_max_health,
+            warrior_2_max_health: battle.warrior_2_max_health,
+            ..battle.v0.into()
+        }
+    }
+}
+
+/// Battle with the energy and cooldowns of the abilities
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct BattleToSaveV2<T = WarriorV3> {
+    pub(crate) v1: BattleToSaveV1<T>,
+    pub(crate) warrior_1_abilities: AbilityState,
+    pub(crate) warrior_2_abilities: AbilityState,
+}
+
+impl<T: Into<Warrior>> From<BattleToSaveV2<T>> for BattleToSave {
+    fn from(battle: BattleToSaveV2<T>) -> Self {
+        BattleToSave {
+            warrior_1_abilities: battle.warrior_1_abilities,
+            warrior_2_abilities: battle.warrior_2_abilities,
+            ..battle.v1.into()
+        }
+    }
+}
+
+/// Battle with the active status effects
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct BattleToSaveV3<T = WarriorV4> {
+    pub(crate) v2: BattleToSaveV2<T>,
+    pub(crate) warrior_1_effects: Vec<StatusEffect>,
+    pub(crate) warrior_2_effects: Vec<StatusEffect>,
+}
+
+impl<T: Into<Warrior>> From<BattleToSaveV3<T>> for BattleToSave {
+    fn from(battle: BattleToSaveV3<T>) -> Self {
+        BattleToSave {
+            warrior_1_effects: battle.warrior_1_effects,
+            warrior_2_effects: battle.warrior_2_effects,
+            ..battle.v2.into()
+        }
+    }
+}
+
+/// Item attributes with the basic stats only
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct ItemAttributesV0 {
+    pub(crate) place: Place,
+    pub(crate) strength: u16,
+    pub(crate) stamina: u16,
+    pub(crate) agility: u16,
+    pub(crate) intuition: u16,
+}
+
+impl From<ItemAttributesV0> for ItemAttributes {
+    fn from(attributes: ItemAttributesV0) -> Self {
+        ItemAttributes {
+            strength: attributes.strength,
+            stamina: attributes.stamina,
+            agility: attributes.agility,
+            intuition: attributes.intuition,
+            ..ItemAttributes::new(attributes.place)
+        }
+    }
+}
+
+/// Item attributes with the attack and protect slots
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct ItemAttributesV1 {
+    pub(crate) v0: ItemAttributesV0,
+    pub(crate) attack_slots: u8,
+    pub(crate) protect_slots: u8,
+}
+
+impl From<ItemAttributesV1> for ItemAttributes {
+    fn from(attributes: ItemAttributesV1) -> Self {
+        ItemAttributes {
+            attack_slots: attributes.attack_slots,
+            protect_slots: attributes.protect_slots,
+            ..attributes.v0.into()
+        }
+    }
+}
+
+/// Item attributes with the armor
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct ItemAttributesV2 {
+    pub(crate) v1: ItemAttributesV1,
+    pub(crate) armor: u16,
+}
+
+impl From<ItemAttributesV2> for ItemAttributes {
+    fn from(attributes: ItemAttributesV2) -> Self {
+        ItemAttributes {
+            armor: attributes.armor,
+            ..attributes.v1.into()
+        }
+    }
+}
+
+/// Item attributes with the abilities
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct ItemAttributesV3 {
+    pub(crate) v2: ItemAttributesV2,
+    pub(crate) abilities: Vec<Ability>,
+}
+
+impl From<ItemAttributesV3> for ItemAttributes {
+    fn from(attributes: ItemAttributesV3) -> Self {
+        ItemAttributes {
+            abilities: attributes.abilities,
+            ..attributes.v2.into()
+        }
+    }
+}
+
+/// Item attributes with the status effect put on a hit
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct ItemAttributesV4 {
+    pub(crate) v3: ItemAttributesV3,
+    pub(crate) hit_effect: Option<StatusEffect>,
+}
+
+impl From<ItemAttributesV4> for ItemAttributes {
+    fn from(attributes: ItemAttributesV4) -> Self {
+        ItemAttributes {
+            hit_effect: attributes.hit_effect,
+            ..attributes.v3.into()
+        }
+    }
+}
+
+/// Item attributes with the damage type and resistances
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct ItemAttributesV5 {
+    pub(crate) v4: ItemAttributesV4,
+    pub(crate) damage_type: Option<DamageType>,
+    pub(crate) resistances: Vec<Resistance>,
+}
+
+impl From<ItemAttributesV5> for ItemAttributes {
+    fn from(attributes: ItemAttributesV5) -> Self {
+        ItemAttributes {
+            damage_type: attributes.damage_type,
+            resistances: attributes.resistances,
+            ..attributes.v4.into()
+        }
+    }
+}
+
+/// Token series and loot items are generic over the attributes, so every attributes layout reuses them
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct TokenSeriesV0<A = ItemAttributesV0> {
+    pub(crate) contract_id: AccountId,
+    pub(crate) token_series_id: TokenSeriesId,
+    pub(crate) metadata: TokenMetadata,
+    pub(crate) attributes: A,
+}
+
+impl<A: Into<ItemAttributes>> From<TokenSeriesV0<A>> for TokenSeries {
+    fn from(token_series: TokenSeriesV0<A>) -> Self {
+        TokenSeries {
+            contract_id: token_series.contract_id,
+            token_series_id: token_series.token_series_id,
+            metadata: token_series.metadata,
+            attributes: token_series.attributes.into(),
+        }
+    }
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct LootItemV0<A = ItemAttributesV0> {
+    pub(crate) metadata: TokenMetadata,
+    pub(crate) attributes: A,
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct RarityTierV0<A = ItemAttributesV0> {
+    pub(crate) name: String,
+    pub(crate) weight: u32,
+    pub(crate) items: Vec<LootItemV0<A>>,
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct LootConfigV0<A = ItemAttributesV0> {
+    pub(crate) drop_chance: u16,
+    pub(crate) daily_cap: u32,
+    pub(crate) tiers: Vec<RarityTierV0<A>>,
+}
+
+impl<A: Into<ItemAttributes>> From<LootConfigV0<A>> for LootConfig {
+    fn from(loot_config: LootConfigV0<A>) -> Self {
+        LootConfig {
+            drop_chance: loot_config.drop_chance,
+            daily_cap: loot_config.daily_cap,
+            tiers: loot_config.tiers.into_iter()
+                .map(|tier| RarityTier {
+                    name: tier.name,
+                    weight: tier.weight,
+                    items: tier.items.into_iter()
+                        .map(|item| LootItem { metadata: item.metadata, attributes: item.attributes.into() })
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Loadouts of old layouts are never converted, they are only read to be rebuilt
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct LoadoutV0<T = WarriorV0> {
+    pub(crate) warrior: T,
+    pub(crate) tokens_series_version: u64,
+}
+
+/// State before the inventories
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct DeFightV1 {
+    pub(crate) owner_ids: UnorderedSet<AccountId>,
+    pub(crate) nft_contracts: UnorderedMap<AccountId, NftContractKind>,
+    pub(crate) tokens_series: UnorderedMap<TokenSeriesKey, ETokenSeries>,
+    pub(crate) battles: LookupMap<BattleId, EBattleToSave>,
+    pub(crate) available_warriors: UnorderedMap<AccountId, EBattleConfig>,
+    pub(crate) warriors_equipment: LookupMap<AccountId, EEquipmentConfig>,
+    pub(crate) loadouts: LookupMap<AccountId, ELoadout>,
+    pub(crate) stats: UnorderedMap<AccountId, EStats>,
+    pub(crate) available_battles: UnorderedMap<BattleId, (AccountId, AccountId)>,
+    pub(crate) next_battle_id: BattleId,
+    pub(crate) service_fee: Balance,
+    pub(crate) tokens_series_version: u64,
+    pub(crate) tokens: NonFungibleToken,
+    pub(crate) metadata: LazyOption<NFTContractMetadata>,
+    pub(crate) native_items: LookupMap<TokenId, EItemAttributes>,
+    pub(crate) next_token_id: u64,
+    pub(crate) loot_config: LazyOption<ELootConfig>,
+    pub(crate) loot_drops: LookupMap<AccountId, LootDrops>,
+    pub(crate) profiles: LookupMap<AccountId, EProfile>,
+    pub(crate) nicknames: LookupMap<String, AccountId>,
+    pub(crate) storage_accounts: LookupMap<AccountId, StorageAccount>,
+    pub(crate) battle_summaries: LookupMap<BattleId, BattleSummary>,
+    pub(crate) battle_archive_age: Timestamp,
+}
+
+/// State with the inventories, before the item sets
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct DeFightV2 {
+    pub(crate) v1: DeFightV1,
+    pub(crate) inventories: LookupMap<AccountId, Inventory>,
+}
+
+pub(crate) fn read_state_version() -> u32 {
+    env::storage_read(STATE_VERSION_KEY)
+        .map_or(0, |value| u32::try_from_slice(&value).expect("Wrong state version"))
+}
+
+pub(crate) fn write_state_version() {
+    env::storage_write(STATE_VERSION_KEY, &STATE_VERSION.try_to_vec().unwrap());
+}
+
+/// Battles and equipment of the first version are not versioned, they are kept under the old prefixes
+/// and are moved to the versioned collections before the next write
+fn legacy_battles() -> LookupMap<BattleId, BattleToSaveV0> {
+    LookupMap::new(StorageKey::Battles)
+}
+
+fn legacy_warriors_equipment() -> LookupMap<AccountId, EquipmentConfigV0> {
+    LookupMap::new(StorageKey::WarriorsEquipment)
+}
+
+fn assert_migration_access(owner_ids: &UnorderedSet<AccountId>) {
+    let predecessor_account_id = env::predecessor_account_id();
+    assert!(
+        owner_ids.contains(&predecessor_account_id) || predecessor_account_id == env::current_account_id(),
+        "ERR_NO_ACCESS"
+    );
+}
+
+impl DeFight {
+    fn migrate_v0(mut old_state: DeFightV0) -> Self {
+        let old_tokens_series = old_state.tokens_series.to_vec();
+        old_state.tokens_series.clear();
+
+        let mut this = DeFight {
+            owner_ids: old_state.owner_ids,
+            available_warriors: old_state.available_warriors,
+            stats: old_state.stats,
+            available_battles: old_state.available_battles,
+            next_battle_id: old_state.next_battle_id,
+            service_fee: old_state.service_fee,
+            ..DeFight::new_state()
+        };
+
+        this.nft_contracts.insert(&PARAS_CONTRACT_ID.to_string(), &NftContractKind::Paras);
+
+        for (token_series_id, token_series) in old_tokens_series {
+            let extra = token_series.metadata.extra.clone().unwrap_or_default();
+            match ItemAttributes::parse(&extra) {
+                Ok(attributes) => {
+                    let key = (PARAS_CONTRACT_ID.to_string(), token_series_id.clone());
+                    this.tokens_series.insert(&key, &ETokenSeries::Current(TokenSeries {
+                        contract_id: PARAS_CONTRACT_ID.to_string(),
+                        token_series_id,
+                        metadata: token_series.metadata,
+                        attributes,
+                    }));
+                },
+                Err(e) => log!("Token series {} is removed: {}", token_series_id, e),
+            }
+        }
+
+        this
+    }
+
+    /// Adds the inventories
+    fn migrate_v1(old_state: DeFightV1) -> DeFightV2 {
+        DeFightV2 {
+            v1: old_state,
+            inventories: LookupMap::new(StorageKey::Inventories),
+        }
+    }
+
+    /// Adds the item sets and their index
+    fn migrate_v2(old_state: DeFightV2) -> Self {
+        let DeFightV2 { v1: old_state, inventories } = old_state;
+        DeFight {
+            owner_ids: old_state.owner_ids,
+            nft_contracts: old_state.nft_contracts,
+            tokens_series: old_state.tokens_series,
+            battles: old_state.battles,
+            available_warriors: old_state.available_warriors,
+            warriors_equipment: old_state.warriors_equipment,
+            loadouts: old_state.loadouts,
+            stats: old_state.stats,
+            available_battles: old_state.available_battles,
+            next_battle_id: old_state.next_battle_id,
+            service_fee: old_state.service_fee,
+            tokens_series_version: old_state.tokens_series_version,
+            tokens: old_state.tokens,
+            metadata: old_state.metadata,
+            native_items: old_state.native_items,
+            next_token_id: old_state.next_token_id,
+            loot_config: old_state.loot_config,
+            loot_drops: old_state.loot_drops,
+            profiles: old_state.profiles,
+            nicknames: old_state.nicknames,
+            storage_accounts: old_state.storage_accounts,
+            battle_summaries: old_state.battle_summaries,
+            battle_archive_age: old_state.battle_archive_age,
+            inventories,
+            item_sets: UnorderedMap::new(StorageKey::ItemSets),
+            item_sets_by_piece: LookupMap::new(StorageKey::ItemSetsByPiece),
+        }
+    }
+
+    pub(crate) fn get_native_item(&self, token_id: &TokenId) -> Option<ItemAttributes> {
+        self.native_items.get(token_id).map(ItemAttributes::from)
+    }
+
+    pub(crate) fn get_loadout(&self, account_id: &AccountId) -> Option<Loadout> {
+        match self.loadouts.get(account_id)? {
+            ELoadout::Current(loadout) => Some(loadout),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn save_loadout(&mut self, account_id: &AccountId, loadout: Loadout) {
+        self.loadouts.insert(account_id, &ELoadout::Current(loadout));
+    }
+
+    pub(crate) fn get_equipment(&self, account_id: &AccountId) -> Option<EquipmentConfig> {
+        self.warriors_equipment.get(account_id).map(EquipmentConfig::from)
+            .or_else(|| legacy_warriors_equipment().get(account_id).map(EquipmentConfig::from))
+    }
+
+    pub(crate) fn save_equipment(&mut self, account_id: &AccountId, equipment: &EquipmentConfig) {
+        self.warriors_equipment.insert(account_id, &EEquipmentConfig::Current(equipment.clone()));
+    }
+
+    /// Moves the equipment of the first version to the versioned equipment. Called before the storage snapshot,
+    /// the contract keeps paying for the equipment it has stored before the storage management.
+    pub(crate) fn migrate_legacy_equipment(&mut self, account_id: &AccountId) {
+        if let Some(equipment) = legacy_warriors_equipment().remove(account_id) {
+            self.save_equipment(account_id, &equipment.into());
+        }
+    }
+
+    pub(crate) fn get_saved_battle(&self, battle_id: &BattleId) -> Option<BattleToSave> {
+        self.battles.get(battle_id).map(BattleToSave::from)
+            .or_else(|| legacy_battles().get(battle_id).map(BattleToSave::from))
+    }
+
+    /// Battles of the first version were not charged in full
+    pub(crate) fn is_legacy_battle(&self, battle_id: &BattleId) -> bool {
+        !self.battles.contains_key(battle_id) && legacy_battles().contains_key(battle_id)
+    }
+
+    pub(crate) fn save_battle(&mut self, battle_id: &BattleId, battle: &BattleToSave) {
+        self.battles.insert(battle_id, &EBattleToSave::Current(battle.clone()));
+    }
+
+    pub(crate) fn remove_saved_battle(&mut self, battle_id: &BattleId) {
+        legacy_battles().remove(battle_id);
+        self.battles.remove(battle_id);
+    }
+}
+
+#[near_bindgen]
+impl DeFight {
+    /// Upgrades the state of any previous version to the current one, called by owners after the deployment
+    #[init(ignore_state)]
+    pub fn migrate() -> Self {
+        let state_version = read_state_version();
+
+        let this = match state_version {
+            0 => {
+                let old_state: DeFightV0 = env::state_read().expect("Contract is not initialized");
+                assert_migration_access(&old_state.owner_ids);
+                DeFight::migrate_v0(old_state)
+            },
+            1..=5 => {
+                let old_state: DeFightV1 = env::state_read().expect("Contract is not initialized");
+                assert_migration_access(&old_state.owner_ids);
+                DeFight::migrate_v2(DeFight::migrate_v1(old_state))
+            },
+            6..=9 => {
+                let old_state: DeFightV2 = env::state_read().expect("Contract is not initialized");
+                assert_migration_access(&old_state.v1.owner_ids);
+                DeFight::migrate_v2(old_state)
+            },
+            STATE_VERSION => {
+                let this: DeFight = env::state_read().expect("Contract is not initialized");
+                assert_migration_access(&this.owner_ids);
+                this
+            },
+            _ => env::panic(format!("Unknown state version {}", state_version).as_bytes()),
+        };
+
+        write_state_version();
+        this
+    }
+
+    pub fn get_state_version(&self) -> u32 {
+        read_state_version()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::archive::DEFAULT_BATTLE_ARCHIVE_AGE;
+    use crate::test_utils::{register_storage, token_metadata};
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::json_types::U64;
+    use near_sdk::{testing_env, MockedBlockchain};
+
+    fn warrior_v0() -> WarriorV0 {
+        WarriorV0 { id: 2, account_id: None, strength: 1, stamina: 1, agility: 1, intuition: 1, health: 10, defense: 1 }
+    }
+
+    fn battle_v0<T>(warrior_1: T, warrior_2: T) -> BattleToSaveV0<T> {
+        BattleToSaveV0 {
+            warrior_1,
+            warrior_2,
+            winner: None,
+            reward: 0,
+            last_action_timestamp: 0,
+            warrior_1_missed_action: false,
+            warrior_2_missed_action: false,
+            warrior_1_last_attack: None,
+            warrior_1_last_protect: None,
+            warrior_2_last_attack: None,
+            warrior_2_last_protect: None,
+        }
+    }
+
+    fn write_state_v0() {
+        let mut owner_ids = UnorderedSet::new(StorageKey::OwnerIds);
+        owner_ids.insert(&accounts(0).into());
+
+        let mut tokens_series = UnorderedMap::new(StorageKey::TokensSeries);
+        for (token_series_id, extra) in [("1", "place:helmet,strength:2"), ("2", "wrong")] {
+            tokens_series.insert(&token_series_id.to_string(), &TokenSeriesJson {
+                token_series_id: token_series_id.to_string(),
+                metadata: TokenMetadata { extra: Some(extra.to_string()), ..token_metadata() },
+                creator_id: accounts(0).into(),
+                royalty: HashMap::new(),
+            });
+        }
+
+        let mut warriors_equipment = legacy_warriors_equipment();
+        warriors_equipment.insert(&accounts(1).into(), &EquipmentConfigV0 {
+            helmet: Some("1:1".to_string()),
+            armor: None,
+            gloves: None,
+            bracers: None,
+            shoulder_pads: None,
+            leggings: None,
+            boots: None,
+            amulet: None,
+            weapon_1: None,
+            weapon_2: None,
+        });
+
+        let warrior_1 = || WarriorV0 { id: 1, account_id: Some(accounts(1).into()), ..warrior_v0() };
+        let mut battles = legacy_battles();
+        battles.insert(&0, &battle_v0(warrior_1(), warrior_v0()));
+        battles.insert(&1, &BattleToSaveV0 { winner: Some(1), ..battle_v0(warrior_1(), warrior_v0()) });
+
+        let account_id: AccountId = accounts(1).into();
+        let mut stats = UnorderedMap::new(StorageKey::Stats);
+        stats.insert(&account_id, &EStats::V0(StatsV0 {
+            referrer_id: None,
+            affiliates: UnorderedSet::new(StorageKey::Affiliates { account_id: account_id.clone() }),
+            battles_num: 1,
+            wins_num: 0,
+            lost_num: 0,
+            total_reward: UnorderedMap::new(StorageKey::TotalRewards { account_id: account_id.clone() }),
+            total_affiliate_reward: UnorderedMap::new(StorageKey::TotalAffiliateRewards { account_id: account_id.clone() }),
+        }));
+
+        env::state_write(&DeFightV0 {
+            owner_ids,
+            tokens_series,
+            battles,
+            available_warriors: UnorderedMap::new(StorageKey::AvailableWarriors),
+            warriors_equipment,
+            stats,
+            available_battles: UnorderedMap::new(StorageKey::AvailableBattles),
+            next_battle_id: 2,
+            service_fee: 0,
+        });
+    }
+
+    #[test]
+    fn test_migrate_v0() {
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(0)).build());
+        write_state_v0();
+
+        let contract = DeFight::migrate();
+        assert_eq!(contract.get_state_version(), STATE_VERSION);
+
+        let token_series = contract.get_token_series(U64(0), U64(10));
+        assert_eq!(token_series.len(), 1);
+        assert_eq!(token_series[0].attributes.place, Place::Helmet);
+
+        let account_id: AccountId = accounts(1).into();
+        assert_eq!(contract.get_equipment(&account_id).unwrap().helmet.unwrap().contract_id, PARAS_CONTRACT_ID);
+        assert_eq!(contract.get_stats(&account_id).battles_num, 1);
+        assert_eq!(contract.get_account_level(&account_id), 1);
+
+        assert_eq!(contract.get_battle(&0).warrior_1.account_id, Some(account_id));
+        assert!(contract.is_legacy_battle(&0));
+        assert_eq!(contract.next_battle_id, 2);
+    }
+
+    #[test]
+    fn test_legacy_data_is_not_credited() {
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(0)).build());
+        write_state_v0();
+        let mut contract = DeFight::migrate();
+        let account_id: AccountId = accounts(1).into();
+        contract.storage_accounts.insert(&account_id, &StorageAccount { deposit: 10u128.pow(24), used_bytes: 0 });
+
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(1)).build());
+        contract.unequip(Place::Helmet);
+        assert!(legacy_warriors_equipment().get(&account_id).is_none());
+        assert_eq!(contract.get_equipment(&account_id).unwrap().helmet, None);
+
+        let used_bytes = contract.storage_accounts.get(&account_id).unwrap().used_bytes;
+
+        testing_env!(VMContextBuilder::new().block_timestamp(archive::DEFAULT_BATTLE_ARCHIVE_AGE).build());
+        assert_eq!(contract.archive_battles(vec![0, 1]), vec![1]);
+        assert!(legacy_battles().get(&1).is_none());
+        assert_eq!(contract.storage_accounts.get(&account_id).unwrap().used_bytes, used_bytes);
+    }
+
+    #[test]
+    fn test_battle_v0_is_converted() {
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(0)).build());
+        let mut contract = DeFight::new();
+        contract.battles.insert(&0, &EBattleToSave::V0(battle_v0(warrior_v0(), warrior_v0())));
+
+        let battle = contract.get_battle(&0);
+        assert_eq!(battle.format, BattleFormat::Elimination);
+        assert_eq!(battle.warrior_1_max_health, 10);
+        assert_eq!(battle.warrior_1.attack_slots, 1);
+    }
+
+    fn item_attributes_v0(place: Place) -> ItemAttributesV0 {
+        ItemAttributesV0 { place, strength: 2, stamina: 0, agility: 0, intuition: 0 }
+    }
+
+    #[test]
+    fn test_read_old_layouts() {
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(0)).build());
+        let mut contract = DeFight::new();
+        let account_id: AccountId = accounts(1).into();
+
+        let warrior_v1 = || WarriorV1 { v0: warrior_v0(), attack_slots: 2, protect_slots: 1 };
+        contract.native_items.insert(&"0".to_string(), &EItemAttributes::V0(item_attributes_v0(Place::Boots)));
+        contract.native_items.insert(&"1".to_string(), &EItemAttributes::V1(ItemAttributesV1 {
+            v0: item_attributes_v0(Place::Helmet),
+            attack_slots: 1,
+            protect_slots: 0,
+        }));
+        contract.battles.insert(&1, &EBattleToSave::V2(BattleToSaveV1 {
+            v0: battle_v0(warrior_v1(), warrior_v1()),
+            format: BattleFormat::BestOf { duels: 3 },
+            round: 1,
+            duel: 1,
+            warrior_1_duel_wins: 1,
+            warrior_2_duel_wins: 0,
+            warrior_1_max_health: 10,
+            warrior_2_max_health: 10,
+        }));
+        contract.native_items.insert(&"2".to_string(), &EItemAttributes::V2(ItemAttributesV2 {
+            v1: ItemAttributesV1 { v0: item_attributes_v0(Place::Armor), attack_slots: 0, protect_slots: 0 },
+            armor: 3,
+        }));
+        contract.battles.insert(&2, &EBattleToSave::V3(BattleToSaveV1 {
+            v0: battle_v0(WarriorV2 { v1: warrior_v1(), armor: [1; 5] }, WarriorV2 { v1: warrior_v1(), armor: [0; 5] }),
+            format: BattleFormat::Elimination,
+            round: 2,
+            duel: 0,
+            warrior_1_duel_wins: 0,
+            warrior_2_duel_wins: 0,
+            warrior_1_max_health: 10,
+            warrior_2_max_health: 10,
+        }));
+        let warrior_v3 = || WarriorV3 {
+            v2: WarriorV2 { v1: warrior_v1(), armor: [1; 5] },
+            abilities: vec![Ability::PowerStrike, Ability::Stun],
+        };
+        contract.battles.insert(&3, &EBattleToSave::V4(BattleToSaveV2 {
+            v1: BattleToSaveV1 {
+                v0: battle_v0(warrior_v3(), warrior_v3()),
+                format: BattleFormat::Elimination,
+                round: 3,
+                duel: 0,
+                warrior_1_duel_wins: 0,
+                warrior_2_duel_wins: 0,
+                warrior_1_max_health: 10,
+                warrior_2_max_health: 10,
+            },
+            warrior_1_abilities: AbilityState { energy: 1, max_energy: 20, ready_at: vec![(Ability::Stun, 4)] },
+            warrior_2_abilities: AbilityState { energy: 20, max_energy: 20, ready_at: vec![] },
+        }));
+        let bleed = StatusEffect { kind: StatusEffectKind::Bleed, rounds: 2, strength: 1 };
+        contract.native_items.insert(&"3".to_string(), &EItemAttributes::V4(ItemAttributesV4 {
+            v3: ItemAttributesV3 {
+                v2: ItemAttributesV2 {
+                    v1: ItemAttributesV1 { v0: item_attributes_v0(Place::Weapon1), attack_slots: 0, protect_slots: 0 },
+                    armor: 0,
+                },
+                abilities: vec![],
+            },
+            hit_effect: Some(bleed.clone()),
+        }));
+        let warrior_v5 = || WarriorV5 {
+            v4: WarriorV4 { v3: warrior_v3(), hit_effects: vec![bleed.clone()] },
+            damage_types: vec![DamageType::Fire],
+            resistances: vec![Resistance { damage_type: DamageType::Frost, value: 10 }],
+        };
+        contract.battles.insert(&4, &EBattleToSave::V6(BattleToSaveV3 {
+            v2: BattleToSaveV2 {
+                v1: BattleToSaveV1 {
+                    v0: battle_v0(warrior_v5(), warrior_v5()),
+                    format: BattleFormat::Elimination,
+                    round: 4,
+                    duel: 0,
+                    warrior_1_duel_wins: 0,
+                    warrior_2_duel_wins: 0,
+                    warrior_1_max_health: 10,
+                    warrior_2_max_health: 10,
+                },
+                warrior_1_abilities: AbilityState { energy: 20, max_energy: 20, ready_at: vec![] },
+                warrior_2_abilities: AbilityState { energy: 20, max_energy: 20, ready_at: vec![] },
+            },
+            warrior_1_effects: vec![],
+            warrior_2_effects: vec![bleed.clone()],
+        }));
+        contract.loadouts.insert(&account_id, &ELoadout::V1(LoadoutV0 { warrior: warrior_v1(), tokens_series_version: 0 }));
+
+        assert_eq!(contract.get_item_attributes("0".to_string()).unwrap().strength, 2);
+        assert_eq!(contract.get_item_attributes("1".to_string()).unwrap().attack_slots, 1);
+        assert_eq!(contract.get_item_attributes("2".to_string()).unwrap().abilities, vec![]);
+        assert_eq!(contract.get_item_attributes("2".to_string()).unwrap().armor, 3);
+        assert_eq!(contract.get_item_attributes("3".to_string()).unwrap().hit_effect, Some(bleed.clone()));
+        let battle = contract.get_battle(&1);
+        assert_eq!((battle.warrior_1.attack_slots, battle.warrior_1.armor), (2, [0; 5]));
+        assert_eq!((battle.format, battle.warrior_1_duel_wins), (BattleFormat::BestOf { duels: 3 }, 1));
+        let battle = contract.get_battle(&2);
+        assert_eq!((battle.warrior_1.armor, battle.round), ([1; 5], 2));
+        assert_eq!(battle.warrior_1_abilities.energy, battle.warrior_1_abilities.max_energy);
+        let battle = contract.get_battle(&3);
+        assert_eq!((battle.warrior_1.armor, battle.warrior_1.abilities.len()), ([1; 5], 2));
+        assert_eq!((battle.warrior_1_abilities.energy, battle.round), (1, 3));
+        assert!(battle.warrior_1_effects.is_empty());
+        let battle = contract.get_battle(&4);
+        assert_eq!((battle.warrior_1.damage_types, battle.warrior_1.damage_bonus), (vec![DamageType::Fire], 0));
+        assert_eq!((battle.warrior_1.hit_effects, battle.warrior_2_effects), (vec![bleed.clone()], vec![bleed]));
+        // Stale loadouts are rebuilt
+        assert!(contract.get_loadout(&account_id).is_none());
+    }
+
+    fn write_state_v1() {
+        env::storage_write(STATE_VERSION_KEY, &1u32.try_to_vec().unwrap());
+
+        let mut owner_ids = UnorderedSet::new(StorageKey::OwnerIds);
+        owner_ids.insert(&accounts(0).into());
+
+        let mut tokens_series = UnorderedMap::new(StorageKey::TokensSeries);
+        tokens_series.insert(&(PARAS_CONTRACT_ID.to_string(), "1".to_string()), &ETokenSeries::V0(TokenSeriesV0 {
+            contract_id: PARAS_CONTRACT_ID.to_string(),
+            token_series_id: "1".to_string(),
+            metadata: token_metadata(),
+            attributes: item_attributes_v0(Place::Helmet),
+        }));
+
+        env::state_write(&DeFightV1 {
+            owner_ids,
+            nft_contracts: UnorderedMap::new(StorageKey::NftContracts),
+            tokens_series,
+            battles: LookupMap::new(StorageKey::BattlesV1),
+            available_warriors: UnorderedMap::new(StorageKey::AvailableWarriors),
+            warriors_equipment: LookupMap::new(StorageKey::WarriorsEquipmentV1),
+            loadouts: LookupMap::new(StorageKey::Loadouts),
+            stats: UnorderedMap::new(StorageKey::Stats),
+            available_battles: UnorderedMap::new(StorageKey::AvailableBattles),
+            next_battle_id: 0,
+            service_fee: 0,
+            tokens_series_version: 1,
+            tokens: items::new_non_fungible_token(),
+            metadata: LazyOption::new(StorageKey::NftMetadata, Some(&NFTContractMetadata {
+                name: "Old items".to_string(),
+                ..items::default_nft_metadata()
+            })),
+            native_items: LookupMap::new(StorageKey::NativeItems),
+            next_token_id: 1,
+            loot_config: LazyOption::new(StorageKey::LootConfig, None),
+            loot_drops: LookupMap::new(StorageKey::LootDrops),
+            profiles: LookupMap::new(StorageKey::Profiles),
+            nicknames: LookupMap::new(StorageKey::Nicknames),
+            storage_accounts: LookupMap::new(StorageKey::StorageAccounts),
+            battle_summaries: LookupMap::new(StorageKey::BattleSummaries),
+            battle_archive_age: DEFAULT_BATTLE_ARCHIVE_AGE,
+        });
+    }
+
+    #[test]
+    fn test_migrate_v1() {
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(0)).build());
+        write_state_v1();
+
+        let mut contract = DeFight::migrate();
+        assert_eq!(contract.get_state_version(), STATE_VERSION);
+        assert_eq!(contract.metadata.get().unwrap().name, "Old items");
+        assert_eq!(contract.get_token_series(U64(0), U64(10))[0].attributes.strength, 2);
+
+        register_storage(&mut contract, &accounts(1).into());
+        contract.give_consumables(accounts(1), Consumable::HealthPotion, 1);
+        assert_eq!(contract.get_inventory(accounts(1).into()).get_amount(Consumable::HealthPotion), 1);
+    }
+
+    #[test]
+    fn test_migrate_v2() {
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(0)).build());
+        write_state_v1();
+        env::state_write(&DeFight::migrate_v1(env::state_read().unwrap()));
+        env::storage_write(STATE_VERSION_KEY, &6u32.try_to_vec().unwrap());
+
+        let mut contract = DeFight::migrate();
+        assert_eq!(contract.get_state_version(), STATE_VERSION);
+        assert_eq!(contract.metadata.get().unwrap().name, "Old items");
+
+        contract.set_item_set("knight".to_string(), ItemSet {
+            pieces: vec![
+                ItemSetPiece { contract_id: PARAS_CONTRACT_ID.to_string(), token_series_id: "1".to_string() },
+                ItemSetPiece { contract_id: PARAS_CONTRACT_ID.to_string(), token_series_id: "2".to_string() },
+            ],
+            bonuses: vec![],
+        });
+        assert_eq!(contract.get_item_sets(U64(0), U64(10)).len(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NO_ACCESS")]
+    fn test_migrate_without_access() {
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(0)).build());
+        write_state_v0();
+
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(1)).build());
+        DeFight::migrate();
+    }
+}
@@ -1,9 +1,39 @@
 use crate::*;
-use strum::{EnumVariantNames, VariantNames};
+use crate::migration::{ItemAttributesV0, ItemAttributesV1, ItemAttributesV2, ItemAttributesV3, ItemAttributesV4, ItemAttributesV5, TokenSeriesV0};
+use strum::EnumVariantNames;
 use std::str::FromStr;
 use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::json_types::U64;
 
 pub type TokenSeriesId = String;
+/// Token series are unique per NFT contract
+pub type TokenSeriesKey = (AccountId, TokenSeriesId);
+
+pub const PARAS_CONTRACT_ID: &str = "paras-token-v2.testnet";
+
+const MAX_ITEM_STAT: u16 = 100;
+const MAX_ITEM_SLOTS: u8 = 1;
+const MAX_ITEM_ARMOR: u16 = 100;
+const MAX_ITEM_ABILITIES: usize = 1;
+
+/// Defines how token ids and item metadata are read from an NFT contract
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, PartialEq, Debug, Copy, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub enum NftContractKind {
+    /// Paras token ids are `{token_series_id}:{edition}`, metadata is stored per series
+    Paras,
+    /// NEP-171 token with its own NEP-177 metadata
+    Nep171,
+}
+
+impl NftContractKind {
+    pub(crate) fn get_token_series_id(&self, token_id: &str) -> TokenSeriesId {
+        match self {
+            NftContractKind::Paras => token_id.split(':').next().unwrap_or_default().to_string(),
+            NftContractKind::Nep171 => token_id.to_string(),
+        }
+    }
+}
 
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug)]
 #[serde(crate = "near_sdk::serde")]
@@ -14,7 +44,216 @@ pub struct TokenSeriesJson {
     pub royalty: HashMap<AccountId, u32>
 }
 
-#[derive(PartialEq, EnumVariantNames, Debug, Copy, Clone)]
+/// Item attributes kept in the token series `extra` field, e.g.
+/// `{"place": "helmet", "strength": 2, "stamina": 1}`.
+/// Unknown fields are ignored so new attributes can be added to the series first.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ItemAttributes {
+    pub place: Place,
+    #[serde(default)]
+    pub strength: u16,
+    #[serde(default)]
+    pub stamina: u16,
+    #[serde(default)]
+    pub agility: u16,
+    #[serde(default)]
+    pub intuition: u16,
+    /// Extra attacks per round given by the item
+    #[serde(default)]
+    pub attack_slots: u8,
+    /// Extra protected parts per round given by the item
+    #[serde(default)]
+    pub protect_slots: u8,
+    /// Reduces damage to the parts covered by the item place
+    #[serde(default)]
+    pub armor: u16,
+    /// Abilities the warrior can use in battles while the item is equipped
+    #[serde(default)]
+    pub abilities: Vec<Ability>,
+    /// Status effect put on the opponent when the warrior hits an unprotected part
+    #[serde(default)]
+    pub hit_effect: Option<StatusEffect>,
+    /// Type of the weapon damage
+    #[serde(default)]
+    pub damage_type: Option<DamageType>,
+    #[serde(default)]
+    pub resistances: Vec<Resistance>,
+    /// Category of the weapon, weapons are one-handed by default
+    #[serde(default)]
+    pub weapon_category: Option<WeaponCategory>,
+}
+
+impl ItemAttributes {
+    pub fn new(place: Place) -> ItemAttributes {
+        ItemAttributes {
+            place,
+            strength: 0,
+            stamina: 0,
+            agility: 0,
+            intuition: 0,
+            attack_slots: 0,
+            protect_slots: 0,
+            armor: 0,
+            abilities: vec![],
+            hit_effect: None,
+            damage_type: None,
+            resistances: vec![],
+            weapon_category: None,
+        }
+    }
+
+    /// Parses the JSON `extra` field. The legacy `place:helmet,strength:2` format is still accepted,
+    /// it supports only the basic stats.
+    pub fn parse(extra: &str) -> Result<ItemAttributes, String> {
+        let attributes = if extra.trim_start().starts_with('{') {
+            near_sdk::serde_json::from_str::<ItemAttributes>(extra).map_err(|e| e.to_string())?
+        } else {
+            ItemAttributes::parse_legacy(extra)?
+        };
+        attributes.validate()?;
+
+        Ok(attributes)
+    }
+
+    fn parse_legacy(extra: &str) -> Result<ItemAttributes, String> {
+        let mut place = None;
+        let mut attributes = HashMap::new();
+
+        for param in extra.split(',') {
+            let mut key_value = param.splitn(2, ':');
+            let key = key_value.next().unwrap_or_default().trim();
+            let value = key_value.next().ok_or(format!("Missing value for '{}'", key))?.trim();
+
+            if key == "place" {
+                place = Some(value);
+            } else {
+                let value = value.parse::<u16>().map_err(|_| format!("Wrong value '{}' for '{}'", value, key))?;
+                attributes.insert(key, value);
+            }
+        }
+
+        let place = place.ok_or("Missing place")?;
+        let place = near_sdk::serde_json::from_value::<Place>(near_sdk::serde_json::Value::String(place.to_string()))
+            .map_err(|_| format!("Wrong place '{}'", place))?;
+
+        Ok(ItemAttributes {
+            strength: attributes.get("strength").copied().unwrap_or(0),
+            stamina: attributes.get("stamina").copied().unwrap_or(0),
+            agility: attributes.get("agility").copied().unwrap_or(0),
+            intuition: attributes.get("intuition").copied().unwrap_or(0),
+            ..ItemAttributes::new(place)
+        })
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        for (name, value) in [
+            ("strength", self.strength),
+            ("stamina", self.stamina),
+            ("agility", self.agility),
+            ("intuition", self.intuition),
+        ] {
+            if value > MAX_ITEM_STAT {
+                return Err(format!("'{}' can't be greater than {}", name, MAX_ITEM_STAT));
+            }
+        }
+
+        if self.attack_slots > MAX_ITEM_SLOTS || self.protect_slots > MAX_ITEM_SLOTS {
+            return Err(format!("Item can't give more than {} extra slots", MAX_ITEM_SLOTS));
+        }
+
+        if self.armor > MAX_ITEM_ARMOR {
+            return Err(format!("'armor' can't be greater than {}", MAX_ITEM_ARMOR));
+        }
+
+        if self.abilities.len() > MAX_ITEM_ABILITIES {
+            return Err(format!("Item can't give more than {} abilities", MAX_ITEM_ABILITIES));
+        }
+
+        if let Some(hit_effect) = &self.hit_effect {
+            hit_effect.validate()?;
+        }
+
+        self.validate_damage_types()?;
+
+        if self.weapon_category.is_some() && !Place::Weapon1.accepts(self.place) {
+            return Err("Only weapons can have a weapon category".to_string());
+        }
+
+        Ok(())
+    }
+}
+
+/// Stored item attributes of any layout, old layouts are converted when the item is read
+#[derive(BorshDeserialize, BorshSerialize)]
+pub enum EItemAttributes {
+    V0(ItemAttributesV0),
+    V1(ItemAttributesV1),
+    V2(ItemAttributesV2),
+    V3(ItemAttributesV3),
+    V4(ItemAttributesV4),
+    V5(ItemAttributesV5),
+    Current(ItemAttributes),
+}
+
+impl From<EItemAttributes> for ItemAttributes {
+    fn from(e_item_attributes: EItemAttributes) -> Self {
+        match e_item_attributes {
+            EItemAttributes::V0(item_attributes) => item_attributes.into(),
+            EItemAttributes::V1(item_attributes) => item_attributes.into(),
+            EItemAttributes::V2(item_attributes) => item_attributes.into(),
+            EItemAttributes::V3(item_attributes) => item_attributes.into(),
+            EItemAttributes::V4(item_attributes) => item_attributes.into(),
+            EItemAttributes::V5(item_attributes) => item_attributes.into(),
+            EItemAttributes::Current(item_attributes) => item_attributes,
+        }
+    }
+}
+
+/// Token series allowed in the game together with the item attributes parsed from its metadata
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TokenSeries {
+    pub contract_id: AccountId,
+    pub token_series_id: TokenSeriesId,
+    pub metadata: TokenMetadata,
+    pub attributes: ItemAttributes,
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub enum ETokenSeries {
+    V0(TokenSeriesV0),
+    V1(TokenSeriesV0<ItemAttributesV1>),
+    V2(TokenSeriesV0<ItemAttributesV2>),
+    V3(TokenSeriesV0<ItemAttributesV3>),
+    V4(TokenSeriesV0<ItemAttributesV4>),
+    V5(TokenSeriesV0<ItemAttributesV5>),
+    Current(TokenSeries),
+}
+
+impl From<ETokenSeries> for TokenSeries {
+    fn from(e_token_series: ETokenSeries) -> Self {
+        match e_token_series {
+            ETokenSeries::V0(token_series) => token_series.into(),
+            ETokenSeries::V1(token_series) => token_series.into(),
+            ETokenSeries::V2(token_series) => token_series.into(),
+            ETokenSeries::V3(token_series) => token_series.into(),
+            ETokenSeries::V4(token_series) => token_series.into(),
+            ETokenSeries::V5(token_series) => token_series.into(),
+            ETokenSeries::Current(token_series) => token_series,
+        }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct EquippedToken {
+    pub contract_id: AccountId,
+    pub token_id: TokenId,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, PartialEq, EnumVariantNames, Debug, Copy, Clone)]
+#[serde(crate = "near_sdk::serde", rename_all = "snake_case")]
 #[strum(serialize_all = "snake_case")]
 pub enum Place {
     Helmet,
@@ -25,6 +264,7 @@ pub enum Place {
     Leggings,
     Boots,
     Amulet,
+    #[serde(alias = "weapon")]
     Weapon1,
     Weapon2
 }
@@ -49,9 +289,64 @@ impl FromStr for Place {
     }
 }
 
-#[derive(BorshSerialize, BorshDeserialize, Debug, Serialize, Deserialize)]
+impl Place {
+    pub const ALL: [Place; 10] = [
+        Place::Helmet, Place::Armor, Place::Gloves, Place::Bracers, Place::ShoulderPads,
+        Place::Leggings, Place::Boots, Place::Amulet, Place::Weapon1, Place::Weapon2,
+    ];
+
+    /// Checks whether an item made for `item_place` fits the slot. Weapons fit both weapon slots.
+    pub(crate) fn accepts(&self, item_place: Place) -> bool {
+        match self {
+            Place::Weapon1 | Place::Weapon2 => matches!(item_place, Place::Weapon1 | Place::Weapon2),
+            _ => *self == item_place,
+        }
+    }
+
+    /// Body parts protected by the armor of an item in the place
+    pub fn covered_parts(&self) -> &'static [Part] {
+        match self {
+            Place::Helmet => &[Part::Head],
+            Place::Amulet | Place::ShoulderPads => &[Part::Neck],
+            Place::Armor => &[Part::Chest, Part::Groin],
+            Place::Leggings => &[Part::Groin, Part::Legs],
+            Place::Boots => &[Part::Legs],
+            Place::Gloves | Place::Bracers | Place::Weapon1 | Place::Weapon2 => &[],
+        }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Serialize, Deserialize, Default, Clone)]
 #[serde(crate = "near_sdk::serde")]
 pub struct EquipmentConfig {
+    pub(crate) helmet: Option<EquippedToken>,
+    pub(crate) armor: Option<EquippedToken>,
+    pub(crate) gloves: Option<EquippedToken>,
+    pub(crate) bracers: Option<EquippedToken>,
+    pub(crate) shoulder_pads: Option<EquippedToken>,
+    pub(crate) leggings: Option<EquippedToken>,
+    pub(crate) boots: Option<EquippedToken>,
+    pub(crate) amulet: Option<EquippedToken>,
+    pub(crate) weapon_1: Option<EquippedToken>,
+    pub(crate) weapon_2: Option<EquippedToken>,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub enum EEquipmentConfig {
+    Current(EquipmentConfig),
+}
+
+impl From<EEquipmentConfig> for EquipmentConfig {
+    fn from(e_equipment_config: EEquipmentConfig) -> Self {
+        match e_equipment_config {
+            EEquipmentConfig::Current(equipment_config) => equipment_config,
+        }
+    }
+}
+
+/// Unversioned equipment of the first contract version, all tokens are from Paras
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct EquipmentConfigV0 {
     pub(crate) helmet: Option<TokenId>,
     pub(crate) armor: Option<TokenId>,
     pub(crate) gloves: Option<TokenId>,
@@ -64,90 +359,308 @@ pub struct EquipmentConfig {
     pub(crate) weapon_2: Option<TokenId>,
 }
 
+impl From<EquipmentConfigV0> for EquipmentConfig {
+    fn from(equipment_config: EquipmentConfigV0) -> Self {
+        let paras_token = |token_id: Option<TokenId>| token_id.map(|token_id| EquippedToken {
+            contract_id: PARAS_CONTRACT_ID.to_string(),
+            token_id,
+        });
+
+        EquipmentConfig {
+            helmet: paras_token(equipment_config.helmet),
+            armor: paras_token(equipment_config.armor),
+            gloves: paras_token(equipment_config.gloves),
+            bracers: paras_token(equipment_config.bracers),
+            shoulder_pads: paras_token(equipment_config.shoulder_pads),
+            leggings: paras_token(equipment_config.leggings),
+            boots: paras_token(equipment_config.boots),
+            amulet: paras_token(equipment_config.amulet),
+            weapon_1: paras_token(equipment_config.weapon_1),
+            weapon_2: paras_token(equipment_config.weapon_2),
+        }
+    }
+}
+
+impl EquipmentConfig {
+    pub(crate) fn slot(&self, place: Place) -> &Option<EquippedToken> {
+        match place {
+            Place::Helmet => &self.helmet,
+            Place::Armor => &self.armor,
+            Place::Gloves => &self.gloves,
+            Place::Bracers => &self.bracers,
+            Place::ShoulderPads => &self.shoulder_pads,
+            Place::Leggings => &self.leggings,
+            Place::Boots => &self.boots,
+            Place::Amulet => &self.amulet,
+            Place::Weapon1 => &self.weapon_1,
+            Place::Weapon2 => &self.weapon_2,
+        }
+    }
+
+    pub(crate) fn slot_mut(&mut self, place: Place) -> &mut Option<EquippedToken> {
+        match place {
+            Place::Helmet => &mut self.helmet,
+            Place::Armor => &mut self.armor,
+            Place::Gloves => &mut self.gloves,
+            Place::Bracers => &mut self.bracers,
+            Place::ShoulderPads => &mut self.shoulder_pads,
+            Place::Leggings => &mut self.leggings,
+            Place::Boots => &mut self.boots,
+            Place::Amulet => &mut self.amulet,
+            Place::Weapon1 => &mut self.weapon_1,
+            Place::Weapon2 => &mut self.weapon_2,
+        }
+    }
+
+    pub(crate) fn is_equipped(&self, token: &EquippedToken) -> bool {
+        Place::ALL.iter().any(|place| self.slot(*place).as_ref() == Some(token))
+    }
+
+    /// Equipped tokens of other NFT contracts, their owners are checked with cross-contract calls
+    pub(crate) fn external_tokens(&self) -> Vec<EquippedToken> {
+        Place::ALL.iter()
+            .filter_map(|place| self.slot(*place).clone())
+            .filter(|token| !DeFight::is_native_token(token))
+            .collect()
+    }
+}
+
 #[near_bindgen]
 impl DeFight {
-    pub fn resolve_paras_token_series(&mut self) {
-        let log_message = format!("Get token series cross-contract callback");
-        env::log(log_message.as_bytes());
+    pub(crate) fn assert_owner(&self) {
+        assert!(self.owner_ids.contains(&env::predecessor_account_id()), "ERR_NO_ACCESS");
+    }
+
+    pub(crate) fn is_native_token(token: &EquippedToken) -> bool {
+        token.contract_id == env::current_account_id()
+    }
+
+    pub(crate) fn get_item(&self, token: &EquippedToken) -> Option<ItemAttributes> {
+        if DeFight::is_native_token(token) {
+            return self.get_native_item(&token.token_id);
+        }
+
+        let kind = self.nft_contracts.get(&token.contract_id)?;
+        let token_series_id = kind.get_token_series_id(&token.token_id);
+
+        self.tokens_series.get(&(token.contract_id.clone(), token_series_id))
+            .map(|token_series| TokenSeries::from(token_series).attributes)
+    }
+
+    pub(crate) fn insert_token_series(&mut self, contract_id: AccountId, token_series_id: TokenSeriesId, metadata: TokenMetadata) {
+        let extra = metadata.extra.as_deref().unwrap_or_else(|| env::panic(b"Token series has no attributes"));
+        let attributes = ItemAttributes::parse(extra)
+            .unwrap_or_else(|e| env::panic(format!("Wrong token series attributes: {}", e).as_bytes()));
+
+        let key = (contract_id.clone(), token_series_id.clone());
+        self.tokens_series.insert(&key, &ETokenSeries::Current(TokenSeries { contract_id, token_series_id, metadata, attributes }));
+        self.tokens_series_version += 1;
+    }
+
+    #[private]
+    pub fn resolve_paras_token_series(&mut self, contract_id: AccountId) {
+        env::log(b"Get token series cross-contract callback");
 
         match env::promise_result(0) {
             PromiseResult::NotReady => unreachable!(),
-            PromiseResult::Failed => env::panic(b"Unable to get user tokens"),
+            PromiseResult::Failed => env::panic(b"Unable to get token series"),
             PromiseResult::Successful(result) => {
-                let token_series = near_sdk::serde_json::from_slice::<TokenSeriesJson>(&result).unwrap();
+                let token_series = near_sdk::serde_json::from_slice::<TokenSeriesJson>(&result)
+                    .unwrap_or_else(|_| env::panic(b"Unable to parse token series"));
 
-                let log_message = format!("User tokens: {:?}", token_series);
+                let log_message = format!("Token series: {:?}", token_series);
                 env::log(log_message.as_bytes());
 
-                self.tokens_series.insert(&token_series.token_series_id, &token_series);
+                self.insert_token_series(contract_id, token_series.token_series_id, token_series.metadata);
+            },
+        }
+    }
+
+    #[private]
+    pub fn resolve_nft_token(&mut self, contract_id: AccountId) {
+        env::log(b"Get token cross-contract callback");
+
+        match env::promise_result(0) {
+            PromiseResult::NotReady => unreachable!(),
+            PromiseResult::Failed => env::panic(b"Unable to get token"),
+            PromiseResult::Successful(result) => {
+                let token = near_sdk::serde_json::from_slice::<Option<Token>>(&result)
+                    .unwrap_or_else(|_| env::panic(b"Unable to parse token"))
+                    .unwrap_or_else(|| env::panic(b"Token not found"));
+                let metadata = token.metadata.unwrap_or_else(|| env::panic(b"Token has no metadata"));
+
+                self.insert_token_series(contract_id, token.token_id, metadata);
             },
         }
     }
 
-    pub fn add_token_series(&mut self, id: String) {
-        assert_eq!(self.owner_ids.contains(&env::predecessor_account_id()), true, "ERR_NO_ACCESS");
-        // let id = &id.to_owned();
+    pub fn add_nft_contract(&mut self, contract_id: AccountId, kind: NftContractKind) {
+        self.assert_owner();
+        self.nft_contracts.insert(&contract_id, &kind);
+        self.tokens_series_version += 1;
+    }
+
+    pub fn remove_nft_contract(&mut self, contract_id: AccountId) {
+        self.assert_owner();
+        self.nft_contracts.remove(&contract_id);
+        self.tokens_series_version += 1;
+    }
+
+    pub fn get_nft_contracts(&self) -> Vec<(AccountId, NftContractKind)> {
+        self.nft_contracts.to_vec()
+    }
+
+    /// `id` is a Paras token series id or a NEP-171 token id depending on the contract kind
+    pub fn add_token_series(&mut self, contract_id: AccountId, id: String) {
+        self.assert_owner();
+        let kind = self.nft_contracts.get(&contract_id).expect("NFT contract is not allowed in the game");
+
         let log_message = format!("Token series id: {:?}", id);
         env::log(log_message.as_bytes());
 
-        ext_paras_receiver::nft_get_series_single(
-            id,
-            &"paras-token-v2.testnet".to_string(), //contract account to make the call to
-            0, //attached deposit
-            30_000_000_000_000,
-        )
-
-        //we then resolve the promise and call nft_resolve_transfer on our own contract
-        .then(ext_self::resolve_paras_token_series(
-            &env::current_account_id(), //contract account to make the call to
-            0, //attached deposit
-            30_000_000_000_000, //GAS attached to the call
-        ));
+        match kind {
+            NftContractKind::Paras => {
+                ext_paras_receiver::nft_get_series_single(
+                    id,
+                    &contract_id, //contract account to make the call to
+                    0, //attached deposit
+                    30_000_000_000_000,
+                )
+                .then(ext_self::resolve_paras_token_series(
+                    contract_id.clone(),
+                    &env::current_account_id(), //contract account to make the call to
+                    0, //attached deposit
+                    30_000_000_000_000, //GAS attached to the call
+                ));
+            },
+            NftContractKind::Nep171 => {
+                ext_nft::nft_token(
+                    id,
+                    &contract_id, //contract account to make the call to
+                    0, //attached deposit
+                    30_000_000_000_000,
+                )
+                .then(ext_self::resolve_nft_token(
+                    contract_id.clone(),
+                    &env::current_account_id(), //contract account to make the call to
+                    0, //attached deposit
+                    30_000_000_000_000, //GAS attached to the call
+                ));
+            },
+        }
     }
 
-    pub fn remove_token_series(&mut self, id: String) {
-        assert_eq!(self.owner_ids.contains(&env::predecessor_account_id()), true, "ERR_NO_ACCESS");
-        // let id = &id.to_owned();
+    pub fn remove_token_series(&mut self, contract_id: AccountId, id: String) {
+        self.assert_owner();
         let log_message = format!("Token series id: {:?}", id);
         env::log(log_message.as_bytes());
 
-        self.tokens_series.remove(&id);
+        self.tokens_series.remove(&(contract_id, id));
+        self.tokens_series_version += 1;
     }
 
-    pub fn get_token_series(self, from_index: u64, limit: u64) -> Vec<(TokenId, TokenSeriesJson)> {
-        let keys = self.tokens_series.keys_as_vector();
+    pub fn get_token_series(&self, from_index: U64, limit: U64) -> Vec<TokenSeries> {
+        let from_index = u64::from(from_index);
         let values = self.tokens_series.values_as_vector();
-        (from_index..std::cmp::min(from_index + limit, keys.len()))
-            .map(|index| {
-                let token_series_json: TokenSeriesJson = values.get(index).unwrap().into();
-                (keys.get(index).unwrap(), token_series_json.into())
-            })
+        (from_index..std::cmp::min(from_index.saturating_add(limit.into()), values.len()))
+            .map(|index| values.get(index).unwrap().into())
             .collect()
     }
 
-    pub fn get_warrior_equipment(self, account_id: AccountId) -> EquipmentConfig {
-        if let Some(equipment) = self.warriors_equipment.get(&account_id) {
-            equipment
-        } else {
-            EquipmentConfig {
-                helmet: None,
-                armor: None,
-                gloves: None,
-                bracers: None,
-                shoulder_pads: None,
-                leggings: None,
-                boots: None,
-                amulet: None,
-                weapon_1: None,
-                weapon_2: None,
-            }
-        }
+    pub fn get_warrior_equipment(&self, account_id: AccountId) -> EquipmentConfig {
+        self.get_equipment(&account_id).unwrap_or_default()
     }
 
     pub fn change_warrior_equipment(&mut self, equipment: EquipmentConfig) {
         let account_id = &env::predecessor_account_id();
+        self.migrate_legacy_equipment(account_id);
+        let initial_storage_usage = env::storage_usage();
+
+        for place in Place::ALL.iter() {
+            if let Some(token) = equipment.slot(*place) {
+                self.assert_can_equip(account_id, *place, token);
+                assert!(
+                    Place::ALL.iter().filter(|other| equipment.slot(**other).as_ref() == Some(token)).count() == 1,
+                    "Token is already equipped in another place"
+                );
+            }
+        }
+        self.assert_weapons_compatible(&equipment);
+        self.save_equipment(account_id, &equipment);
+        self.refresh_loadout(account_id);
+        self.charge_storage(account_id, initial_storage_usage);
+    }
+
+    pub fn equip(&mut self, place: Place, contract_id: AccountId, token_id: TokenId) {
+        let account_id = env::predecessor_account_id();
+        self.migrate_legacy_equipment(&account_id);
+        let initial_storage_usage = env::storage_usage();
+        let mut equipment = self.get_equipment(&account_id).unwrap_or_default();
+
+        let token = EquippedToken { contract_id, token_id };
+        self.assert_can_equip(&account_id, place, &token);
+        assert!(
+            equipment.slot(place).as_ref() == Some(&token) || !equipment.is_equipped(&token),
+            "Token is already equipped in another place"
+        );
+
+        *equipment.slot_mut(place) = Some(token.clone());
+        self.assert_weapons_compatible(&equipment);
+        self.save_equipment(&account_id, &equipment);
+        self.refresh_loadout(&account_id);
+        self.charge_storage(&account_id, initial_storage_usage);
+
+        emit_event("equip", EquipmentEventData { account_id, place, token: Some(token) });
+    }
+
+    pub fn unequip(&mut self, place: Place) {
+        let account_id = env::predecessor_account_id();
+        self.migrate_legacy_equipment(&account_id);
+        let initial_storage_usage = env::storage_usage();
+        let mut equipment = self.get_equipment(&account_id).unwrap_or_default();
+
+        let token = equipment.slot_mut(place).take();
+        assert!(token.is_some(), "Nothing is equipped as {:?}", place);
+        self.save_equipment(&account_id, &equipment);
+        self.refresh_loadout(&account_id);
+        self.charge_storage(&account_id, initial_storage_usage);
 
-        // TO DO Data verification for each field of stricture
-        self.warriors_equipment.insert(account_id, &equipment);
+        emit_event("unequip", EquipmentEventData { account_id, place, token });
     }
-}
\ No newline at end of file
+
+    /// Checks that the token is allowed in the place and owned by the account if it's native
+    pub(crate) fn assert_can_equip(&self, account_id: &AccountId, place: Place, token: &EquippedToken) {
+        let attributes = self.get_item(token).expect("Token series is not allowed in the game");
+        assert!(place.accepts(attributes.place), "Token can't be equipped as {:?}", place);
+        assert!(
+            !DeFight::is_native_token(token) || self.is_native_token_owner(account_id, &token.token_id),
+            "Token is not owned by the account"
+        );
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_item_attributes() {
+        let attributes = ItemAttributes::parse(r#"{"place": "weapon", "strength": 3, "rarity": "epic"}"#).unwrap();
+        assert_eq!(attributes.place, Place::Weapon1);
+        assert_eq!(attributes.strength, 3);
+        assert_eq!(attributes.stamina, 0);
+
+        let legacy = ItemAttributes::parse("place:shoulder_pads,agility:2,intuition:1").unwrap();
+        assert_eq!(legacy.place, Place::ShoulderPads);
+        assert_eq!((legacy.agility, legacy.intuition), (2, 1));
+    }
+
+    #[test]
+    fn test_parse_wrong_item_attributes() {
+        assert!(ItemAttributes::parse("place:helmet,strength").is_err());
+        assert!(ItemAttributes::parse("place:helmet,strength:a").is_err());
+        assert!(ItemAttributes::parse("strength:1").is_err());
+        assert!(ItemAttributes::parse("place:hat").is_err());
+        assert!(ItemAttributes::parse(r#"{"place": "helmet", "strength": 1000}"#).is_err());
+        assert!(ItemAttributes::parse(r#"{"strength": 1}"#).is_err());
+    }
+}
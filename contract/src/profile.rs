@@ -0,0 +1,280 @@
+use crate::*;
+
+const RESPEC_PRICE: Balance = 100_000_000_000_000_000_000_000;
+
+const MIN_NICKNAME_LEN: usize = 3;
+const MAX_NICKNAME_LEN: usize = 20;
+const RESERVED_NICKNAMES: [&str; 7] = ["admin", "bot", "defight", "moderator", "owner", "support", "system"];
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StatPoints {
+    pub strength: u16,
+    pub stamina: u16,
+    pub agility: u16,
+    pub intuition: u16,
+}
+
+impl StatPoints {
+    pub fn total(&self) -> u16 {
+        self.strength + self.stamina + self.agility + self.intuition
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub enum EProfile {
+    Current(Profile),
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Profile {
+    pub allocated_stats: StatPoints,
+    pub respecs_num: u32,
+    pub nickname: Option<String>,
+    pub avatar: Option<EquippedToken>,
+    /// Set when the nickname is registered for the first time
+    pub created_at: Option<Timestamp>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BattleView {
+    pub battle: BattleToSave,
+    pub warrior_1_profile: Option<Profile>,
+    pub warrior_2_profile: Option<Profile>,
+}
+
+/// Nicknames are unique case-insensitively, so they are stored in lowercase
+fn get_nickname_key(nickname: &str) -> String {
+    nickname.to_lowercase()
+}
+
+fn assert_valid_nickname(nickname: &str) {
+    assert!(
+        (MIN_NICKNAME_LEN..=MAX_NICKNAME_LEN).contains(&nickname.len()),
+        "Nickname must be from {} to {} characters long", MIN_NICKNAME_LEN, MAX_NICKNAME_LEN
+    );
+    assert!(
+        nickname.starts_with(|c: char| c.is_ascii_alphabetic())
+            && nickname.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'),
+        "Nickname must start with a letter and contain only letters, digits and '_'"
+    );
+    assert!(!RESERVED_NICKNAMES.contains(&get_nickname_key(nickname).as_str()), "Nickname is reserved");
+}
+
+impl From<EProfile> for Profile {
+    fn from(e_profile: EProfile) -> Self {
+        match e_profile {
+            EProfile::Current(profile) => profile,
+        }
+    }
+}
+
+impl Warrior {
+    pub(crate) fn apply_profile(&mut self, profile: &Profile) {
+        let stats = &profile.allocated_stats;
+        self.add_stats(stats.strength, stats.stamina, stats.agility, stats.intuition);
+    }
+}
+
+#[near_bindgen]
+impl DeFight {
+    pub(crate) fn get_profile(&self, account_id: &AccountId) -> Profile {
+        self.profiles.get(account_id).map(Profile::from).unwrap_or_default()
+    }
+
+    pub(crate) fn get_unspent_stat_points(&self, account_id: &AccountId, profile: &Profile) -> u16 {
+        get_level_stat_points(self.get_account_level(account_id)).saturating_sub(profile.allocated_stats.total())
+    }
+
+    pub fn allocate_stats(&mut self, stats: StatPoints) {
+        let account_id = env::predecessor_account_id();
+        let initial_storage_usage = env::storage_usage();
+        let mut profile = self.get_profile(&account_id);
+
+        assert!(stats.total() > 0, "Nothing to allocate");
+        assert!(
+            stats.total() <= self.get_unspent_stat_points(&account_id, &profile),
+            "Not enough unspent stat points"
+        );
+
+        profile.allocated_stats.strength += stats.strength;
+        profile.allocated_stats.stamina += stats.stamina;
+        profile.allocated_stats.agility += stats.agility;
+        profile.allocated_stats.intuition += stats.intuition;
+        self.profiles.insert(&account_id, &EProfile::Current(profile));
+        self.charge_storage(&account_id, initial_storage_usage);
+
+        emit_event("allocate_stats", StatsEventData { account_id, stats });
+    }
+
+    /// Resets all allocated stat points for the fixed price
+    #[payable]
+    pub fn respec(&mut self) {
+        let account_id = env::predecessor_account_id();
+        let initial_storage_usage = env::storage_usage();
+        let deposit = env::attached_deposit();
+        assert!(deposit >= RESPEC_PRICE, "Respec costs {} yoctoNEAR", RESPEC_PRICE);
+
+        let mut profile = self.get_profile(&account_id);
+        assert!(profile.allocated_stats.total() > 0, "No allocated stat points");
+
+        let stats = std::mem::take(&mut profile.allocated_stats);
+        profile.respecs_num += 1;
+        self.profiles.insert(&account_id, &EProfile::Current(profile));
+        self.charge_storage(&account_id, initial_storage_usage);
+
+        if deposit > RESPEC_PRICE {
+            Promise::new(account_id.clone()).transfer(deposit - RESPEC_PRICE);
+        }
+
+        emit_event("respec", StatsEventData { account_id, stats });
+    }
+
+    /// Sets the nickname and the avatar, the used storage is charged from the storage balance
+    pub fn update_profile(&mut self, nickname: String, avatar: Option<EquippedToken>) {
+        let account_id = env::predecessor_account_id();
+        let initial_storage_usage = env::storage_usage();
+
+        assert_valid_nickname(&nickname);
+        let nickname_key = get_nickname_key(&nickname);
+        if let Some(nickname_owner_id) = self.nicknames.get(&nickname_key) {
+            assert_eq!(nickname_owner_id, account_id, "Nickname is already taken");
+        }
+
+        if let Some(avatar) = &avatar {
+            if DeFight::is_native_token(avatar) {
+                assert!(self.is_native_token_owner(&account_id, &avatar.token_id), "Avatar is not owned by the account");
+            } else {
+                assert!(self.nft_contracts.get(&avatar.contract_id).is_some(), "NFT contract is not allowed in the game");
+            }
+        }
+
+        let mut profile = self.get_profile(&account_id);
+        if let Some(old_nickname) = &profile.nickname {
+            self.nicknames.remove(&get_nickname_key(old_nickname));
+        }
+        self.nicknames.insert(&nickname_key, &account_id);
+
+        profile.nickname = Some(nickname);
+        profile.avatar = avatar;
+        profile.created_at.get_or_insert(env::block_timestamp());
+        self.profiles.insert(&account_id, &EProfile::Current(profile));
+        self.charge_storage(&account_id, initial_storage_usage);
+    }
+
+    pub fn get_account_profile(&self, account_id: AccountId) -> Profile {
+        self.get_profile(&account_id)
+    }
+
+    pub fn get_account_by_nickname(&self, nickname: String) -> Option<AccountId> {
+        self.nicknames.get(&get_nickname_key(&nickname))
+    }
+
+    pub fn get_battle_view(&self, battle_id: BattleId) -> BattleView {
+        let battle = self.get_saved_battle(&battle_id).expect("Battle not found");
+        let get_warrior_profile = |warrior: &Warrior| warrior.account_id.as_ref()
+            .and_then(|account_id| self.profiles.get(account_id))
+            .map(Profile::from);
+
+        BattleView {
+            warrior_1_profile: get_warrior_profile(&battle.warrior_1),
+            warrior_2_profile: get_warrior_profile(&battle.warrior_2),
+            battle,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::register_storage;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::{testing_env, MockedBlockchain};
+
+    fn setup_contract() -> DeFight {
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(0)).build());
+        let mut contract = DeFight::new();
+        contract.add_experience(&accounts(1).into(), 250);
+        register_storage(&mut contract, &accounts(1).into());
+        register_storage(&mut contract, &accounts(2).into());
+
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(1)).build());
+        contract
+    }
+
+    #[test]
+    fn test_allocate_stats_and_respec() {
+        let mut contract = setup_contract();
+
+        contract.allocate_stats(StatPoints { strength: 2, stamina: 1, agility: 0, intuition: 0 });
+        contract.allocate_stats(StatPoints { strength: 0, stamina: 0, agility: 3, intuition: 0 });
+        assert_eq!(contract.get_progress(accounts(1).into()).stat_points, 0);
+        assert_eq!(contract.get_warrior(accounts(1).into()).agility, BASE_AGILITY + 3);
+
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(1)).attached_deposit(RESPEC_PRICE).build());
+        contract.respec();
+        assert_eq!(contract.get_progress(accounts(1).into()).stat_points, 6);
+        assert_eq!(contract.get_account_profile(accounts(1).into()).respecs_num, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Not enough unspent stat points")]
+    fn test_allocate_too_many_stats() {
+        let mut contract = setup_contract();
+
+        contract.allocate_stats(StatPoints { strength: 7, stamina: 0, agility: 0, intuition: 0 });
+    }
+
+    #[test]
+    #[should_panic(expected = "Respec costs")]
+    fn test_respec_without_deposit() {
+        let mut contract = setup_contract();
+
+        contract.allocate_stats(StatPoints { strength: 1, stamina: 0, agility: 0, intuition: 0 });
+        contract.respec();
+    }
+
+    #[test]
+    fn test_update_profile() {
+        let mut contract = setup_contract();
+
+        contract.update_profile("Warrior_1".to_string(), None);
+        contract.update_profile("Warrior_2".to_string(), None);
+
+        let profile = contract.get_account_profile(accounts(1).into());
+        assert_eq!(profile.nickname, Some("Warrior_2".to_string()));
+        assert!(profile.created_at.is_some());
+        assert_eq!(contract.get_account_by_nickname("warrior_2".to_string()), Some(accounts(1).into()));
+        assert_eq!(contract.get_account_by_nickname("Warrior_1".to_string()), None);
+        assert!(contract.storage_accounts.get(&accounts(1).into()).unwrap().used_bytes > 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "is not registered")]
+    fn test_update_profile_without_storage() {
+        let mut contract = setup_contract();
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(3)).build());
+
+        contract.update_profile("Warrior".to_string(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Nickname is already taken")]
+    fn test_update_profile_taken_nickname() {
+        let mut contract = setup_contract();
+        contract.update_profile("Warrior".to_string(), None);
+
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(2)).build());
+        contract.update_profile("WARRIOR".to_string(), None);
+    }
+
+    #[test]
+    fn test_nickname_validation() {
+        for nickname in ["ab", "1warrior", "war rior", "Admin", "a_very_long_nickname_1"] {
+            assert!(std::panic::catch_unwind(|| assert_valid_nickname(nickname)).is_err(), "{}", nickname);
+        }
+        assert_valid_nickname("Garik_2");
+    }
+}
@@ -0,0 +1,123 @@
+use crate::*;
+
+/// Experience needed to reach the level, the first level is reached from the start
+const LEVEL_EXPERIENCE: [u64; 10] = [0, 100, 250, 500, 1_000, 2_000, 3_500, 5_500, 8_000, 11_000];
+const STAT_POINTS_PER_LEVEL: u16 = 3;
+const HEALTH_PER_LEVEL: u16 = 5;
+
+const WIN_EXPERIENCE: u64 = 30;
+const DRAW_EXPERIENCE: u64 = 15;
+const LOSS_EXPERIENCE: u64 = 5;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Progress {
+    pub experience: u64,
+    pub level: u16,
+    pub stat_points: u16,
+    pub next_level_experience: Option<u64>,
+}
+
+pub fn get_level(experience: u64) -> u16 {
+    LEVEL_EXPERIENCE.iter().filter(|level_experience| experience >= **level_experience).count() as u16
+}
+
+/// Stat points granted by all reached levels
+pub fn get_level_stat_points(level: u16) -> u16 {
+    level.saturating_sub(1) * STAT_POINTS_PER_LEVEL
+}
+
+fn get_power(warrior: &Warrior) -> u64 {
+    (warrior.strength + warrior.stamina + warrior.agility + warrior.intuition) as u64
+}
+
+/// Experience for the battle outcome scaled by the opponent power from x0.5 to x2
+pub fn get_battle_experience(warrior: &Warrior, opponent: &Warrior, winner: u32) -> u64 {
+    let base_experience = if winner == warrior.id {
+        WIN_EXPERIENCE
+    } else if winner == opponent.id {
+        LOSS_EXPERIENCE
+    } else {
+        DRAW_EXPERIENCE
+    };
+
+    let experience = base_experience * get_power(opponent) / std::cmp::max(get_power(warrior), 1);
+
+    experience.clamp(base_experience / 2, base_experience * 2)
+}
+
+impl Warrior {
+    pub(crate) fn apply_level(&mut self, level: u16) {
+        self.health += level.saturating_sub(1) * HEALTH_PER_LEVEL;
+    }
+}
+
+#[near_bindgen]
+impl DeFight {
+    pub(crate) fn get_account_level(&self, account_id: &AccountId) -> u16 {
+        self.stats.get(account_id)
+            .map_or(1, |stats| get_level(Stats::from(stats).experience))
+    }
+
+    pub(crate) fn add_experience(&mut self, account_id: &AccountId, experience: u64) {
+        let mut stats = self.get_stats(account_id);
+        let level = get_level(stats.experience);
+
+        stats.experience += experience;
+        let new_level = get_level(stats.experience);
+        self.stats.insert(account_id, &EStats::Current(stats));
+
+        if new_level > level {
+            self.refresh_loadout(account_id);
+            emit_event("level_up", LevelUpEventData { account_id: account_id.clone(), level: new_level });
+        }
+    }
+
+    /// Experience is earned by the player only, bots don't have accounts
+    pub(crate) fn add_battle_experience(&mut self, battle: &BattleToSave) {
+        if let (Some(winner), Some(account_id)) = (battle.winner, &battle.warrior_1.account_id) {
+            let experience = get_battle_experience(&battle.warrior_1, &battle.warrior_2, winner);
+            self.add_experience(account_id, experience);
+        }
+    }
+
+    pub fn get_progress(&self, account_id: AccountId) -> Progress {
+        let experience = self.stats.get(&account_id).map_or(0, |stats| Stats::from(stats).experience);
+        let level = get_level(experience);
+        let profile = self.get_profile(&account_id);
+
+        Progress {
+            experience,
+            level,
+            stat_points: self.get_unspent_stat_points(&account_id, &profile),
+            next_level_experience: LEVEL_EXPERIENCE.get(level as usize).copied(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_level() {
+        assert_eq!(get_level(0), 1);
+        assert_eq!(get_level(99), 1);
+        assert_eq!(get_level(100), 2);
+        assert_eq!(get_level(1_000_000), LEVEL_EXPERIENCE.len() as u16);
+        assert_eq!(get_level_stat_points(3), 2 * STAT_POINTS_PER_LEVEL);
+    }
+
+    #[test]
+    fn test_get_battle_experience() {
+        let warrior = Warrior::new(1, None);
+        let mut opponent = Warrior::new(2, None);
+
+        assert_eq!(get_battle_experience(&warrior, &opponent, 1), WIN_EXPERIENCE);
+        assert_eq!(get_battle_experience(&warrior, &opponent, 2), LOSS_EXPERIENCE);
+        assert_eq!(get_battle_experience(&warrior, &opponent, 0), DRAW_EXPERIENCE);
+
+        opponent.add_stats(20, 0, 0, 0);
+        assert_eq!(get_battle_experience(&warrior, &opponent, 1), WIN_EXPERIENCE * 2);
+    }
+}
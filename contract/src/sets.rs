@@ -0,0 +1,221 @@
+use crate::*;
+use near_sdk::json_types::U64;
+
+/// Set bonuses add up with the items, so a bonus stat is bounded like an item stat
+const MAX_SET_BONUS_STAT: u16 = 100;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ItemSetPiece {
+    pub contract_id: AccountId,
+    pub token_series_id: TokenSeriesId,
+}
+
+/// Bonus of the set given when at least `pieces` different pieces are equipped
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SetBonus {
+    pub pieces: u8,
+    #[serde(default)]
+    pub strength: u16,
+    #[serde(default)]
+    pub stamina: u16,
+    #[serde(default)]
+    pub agility: u16,
+    #[serde(default)]
+    pub intuition: u16,
+    #[serde(default)]
+    pub abilities: Vec<Ability>,
+}
+
+/// Token series that give the bonuses when equipped together
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ItemSet {
+    pub pieces: Vec<ItemSetPiece>,
+    pub bonuses: Vec<SetBonus>,
+}
+
+impl ItemSet {
+    fn assert_valid(&self) {
+        assert!(self.pieces.len() >= 2, "Set must have at least 2 pieces");
+        for (index, piece) in self.pieces.iter().enumerate() {
+            assert!(!self.pieces[..index].contains(piece), "Piece {} is repeated in the set", piece.token_series_id);
+        }
+
+        for bonus in &self.bonuses {
+            assert!(
+                bonus.pieces >= 2 && bonus.pieces as usize <= self.pieces.len(),
+                "Bonus must require from 2 to {} pieces", self.pieces.len()
+            );
+            for value in [bonus.strength, bonus.stamina, bonus.agility, bonus.intuition] {
+                assert!(value <= MAX_SET_BONUS_STAT, "Bonus stat can't be greater than {}", MAX_SET_BONUS_STAT);
+            }
+        }
+    }
+
+    /// Bonuses of the set for the equipped token series
+    fn get_active_bonuses(&self, equipped_series: &[ItemSetPiece]) -> Vec<&SetBonus> {
+        let equipped_pieces = self.pieces.iter().filter(|piece| equipped_series.contains(piece)).count();
+        self.bonuses.iter().filter(|bonus| bonus.pieces as usize <= equipped_pieces).collect()
+    }
+}
+
+impl Warrior {
+    pub(crate) fn apply_set_bonus(&mut self, bonus: &SetBonus) {
+        self.add_stats(bonus.strength, bonus.stamina, bonus.agility, bonus.intuition);
+        for ability in &bonus.abilities {
+            self.add_ability(*ability);
+        }
+    }
+}
+
+impl DeFight {
+    fn get_item_set_piece(&self, token: &EquippedToken) -> Option<ItemSetPiece> {
+        if DeFight::is_native_token(token) {
+            return None;
+        }
+
+        let kind = self.nft_contracts.get(&token.contract_id)?;
+        Some(ItemSetPiece { contract_id: token.contract_id.clone(), token_series_id: kind.get_token_series_id(&token.token_id) })
+    }
+
+    /// Applies the bonuses of all sets with enough equipped pieces, `equipped_tokens` are the tokens in the right places
+    pub(crate) fn apply_set_bonuses(&self, warrior: &mut Warrior, equipped_tokens: &[EquippedToken]) {
+        let equipped_series: Vec<ItemSetPiece> = equipped_tokens.iter()
+            .filter_map(|token| self.get_item_set_piece(token))
+            .collect();
+
+        let mut set_ids: Vec<String> = equipped_series.iter()
+            .flat_map(|piece| self.item_sets_by_piece.get(piece).unwrap_or_default())
+            .collect();
+        set_ids.sort();
+        set_ids.dedup();
+
+        for item_set in set_ids.iter().filter_map(|set_id| self.item_sets.get(set_id)) {
+            for bonus in item_set.get_active_bonuses(&equipped_series) {
+                warrior.apply_set_bonus(bonus);
+            }
+        }
+    }
+
+    fn index_item_set(&mut self, set_id: &str, item_set: &ItemSet) {
+        for piece in &item_set.pieces {
+            let mut set_ids = self.item_sets_by_piece.get(piece).unwrap_or_default();
+            set_ids.push(set_id.to_string());
+            self.item_sets_by_piece.insert(piece, &set_ids);
+        }
+    }
+
+    fn unindex_item_set(&mut self, set_id: &str, item_set: &ItemSet) {
+        for piece in &item_set.pieces {
+            let mut set_ids = self.item_sets_by_piece.get(piece).unwrap_or_default();
+            set_ids.retain(|indexed_set_id| indexed_set_id != set_id);
+            if set_ids.is_empty() {
+                self.item_sets_by_piece.remove(piece);
+            } else {
+                self.item_sets_by_piece.insert(piece, &set_ids);
+            }
+        }
+    }
+}
+
+#[near_bindgen]
+impl DeFight {
+    /// Adds or replaces the set, the cached warriors are rebuilt with the new bonuses.
+    /// Pieces are token series of the NFT contracts, native items can't be pieces.
+    pub fn set_item_set(&mut self, set_id: String, item_set: ItemSet) {
+        self.assert_owner();
+        item_set.assert_valid();
+        // Every native item has own attributes and no token series, so it never matches a piece
+        assert!(
+            item_set.pieces.iter().all(|piece| piece.contract_id != env::current_account_id()),
+            "Native items can't be set pieces"
+        );
+
+        if let Some(old_item_set) = self.item_sets.insert(&set_id, &item_set) {
+            self.unindex_item_set(&set_id, &old_item_set);
+        }
+        self.index_item_set(&set_id, &item_set);
+        self.tokens_series_version += 1;
+    }
+
+    pub fn remove_item_set(&mut self, set_id: String) {
+        self.assert_owner();
+        let item_set = self.item_sets.remove(&set_id).unwrap_or_else(|| env::panic(format!("Set {} doesn't exist", set_id).as_bytes()));
+        self.unindex_item_set(&set_id, &item_set);
+        self.tokens_series_version += 1;
+    }
+
+    pub fn get_item_sets(&self, from_index: U64, limit: U64) -> Vec<(String, ItemSet)> {
+        let from_index = u64::from(from_index);
+        let keys = self.item_sets.keys_as_vector();
+        let values = self.item_sets.values_as_vector();
+        (from_index..std::cmp::min(from_index.saturating_add(limit.into()), keys.len()))
+            .map(|index| (keys.get(index).unwrap(), values.get(index).unwrap()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::setup_contract;
+
+    fn piece(token_series_id: &str) -> ItemSetPiece {
+        ItemSetPiece { contract_id: PARAS_CONTRACT_ID.to_string(), token_series_id: token_series_id.to_string() }
+    }
+
+    fn bonus(pieces: u8, strength: u16) -> SetBonus {
+        SetBonus { pieces, strength, stamina: 0, agility: 0, intuition: 0, abilities: vec![] }
+    }
+
+    #[test]
+    fn test_active_bonuses() {
+        let item_set = ItemSet {
+            pieces: vec![piece("1"), piece("2"), piece("3")],
+            bonuses: vec![bonus(2, 1), bonus(3, 2)],
+        };
+
+        assert!(item_set.get_active_bonuses(&[piece("1"), piece("4")]).is_empty());
+        assert_eq!(item_set.get_active_bonuses(&[piece("1"), piece("3")]), vec![&bonus(2, 1)]);
+        assert_eq!(item_set.get_active_bonuses(&[piece("3"), piece("2"), piece("1")]).len(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "Piece 1 is repeated in the set")]
+    fn test_set_with_repeated_piece() {
+        ItemSet { pieces: vec![piece("1"), piece("2"), piece("1")], bonuses: vec![bonus(3, 1)] }.assert_valid();
+    }
+
+    #[test]
+    #[should_panic(expected = "Native items can't be set pieces")]
+    fn test_set_with_native_piece() {
+        let mut contract = setup_contract();
+        let native_piece = ItemSetPiece { contract_id: env::current_account_id(), token_series_id: "1".to_string() };
+        contract.set_item_set("knight".to_string(), ItemSet { pieces: vec![piece("1"), native_piece], bonuses: vec![] });
+    }
+
+    #[test]
+    fn test_get_item_sets_page_past_the_end() {
+        let mut contract = setup_contract();
+        contract.set_item_set("knight".to_string(), ItemSet { pieces: vec![piece("1"), piece("2")], bonuses: vec![] });
+        contract.set_item_set("warlord".to_string(), ItemSet { pieces: vec![piece("2"), piece("3")], bonuses: vec![] });
+
+        let item_sets = contract.get_item_sets(U64(1), U64(u64::MAX));
+        assert_eq!(item_sets.len(), 1);
+        assert_eq!(item_sets[0].0, "warlord".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Bonus stat can't be greater than 100")]
+    fn test_set_bonus_too_big() {
+        ItemSet { pieces: vec![piece("1"), piece("2")], bonuses: vec![bonus(2, u16::MAX)] }.assert_valid();
+    }
+
+    #[test]
+    #[should_panic(expected = "Bonus must require from 2 to 2 pieces")]
+    fn test_bonus_with_too_many_pieces() {
+        ItemSet { pieces: vec![piece("1"), piece("2")], bonuses: vec![bonus(3, 1)] }.assert_valid();
+    }
+}
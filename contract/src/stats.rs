@@ -1,10 +1,23 @@
 use crate::*;
 
+/// Stats are upgraded on read, `V0` is the layout before the experience was added
 #[derive(BorshSerialize, BorshDeserialize)]
 pub enum EStats {
+    V0(StatsV0),
     Current(Stats),
 }
 
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct StatsV0 {
+    pub referrer_id: Option<AccountId>,
+    pub affiliates: UnorderedSet<AccountId>,
+    pub battles_num: u64,
+    pub wins_num: u64,
+    pub lost_num: u64,
+    pub total_reward: UnorderedMap<Option<AccountId>, Balance>,
+    pub total_affiliate_reward: UnorderedMap<Option<AccountId>, Balance>,
+}
+
 #[derive(BorshSerialize, BorshDeserialize)]
 pub struct Stats {
     pub referrer_id: Option<AccountId>,
@@ -12,6 +25,7 @@ pub struct Stats {
     pub battles_num: u64,
     pub wins_num: u64,
     pub lost_num: u64,
+    pub experience: u64,
     pub total_reward: UnorderedMap<Option<AccountId>, Balance>,
     pub total_affiliate_reward: UnorderedMap<Option<AccountId>, Balance>,
 }
@@ -19,6 +33,16 @@ pub struct Stats {
 impl From<EStats> for Stats {
     fn from(e_stats: EStats) -> Self {
         match e_stats {
+            EStats::V0(stats) => Stats {
+                referrer_id: stats.referrer_id,
+                affiliates: stats.affiliates,
+                battles_num: stats.battles_num,
+                wins_num: stats.wins_num,
+                lost_num: stats.lost_num,
+                experience: 0,
+                total_reward: stats.total_reward,
+                total_affiliate_reward: stats.total_affiliate_reward,
+            },
             EStats::Current(stats) => stats,
         }
     }
@@ -32,6 +56,7 @@ impl Stats {
             battles_num: 0,
             wins_num: 0,
             lost_num: 0,
+            experience: 0,
             total_reward: UnorderedMap::new(StorageKey::TotalRewards { account_id: account_id.clone() }),
             total_affiliate_reward: UnorderedMap::new(StorageKey::TotalAffiliateRewards { account_id: account_id.clone() }),
         }
@@ -0,0 +1,171 @@
+use crate::*;
+use near_contract_standards::storage_management::{StorageBalance, StorageBalanceBounds, StorageManagement};
+use near_sdk::json_types::{ValidAccountId, U128};
+use near_sdk::{assert_one_yocto, StorageUsage};
+
+/// Covers the registration itself, the player data is charged by the used bytes
+const MIN_STORAGE_BALANCE: Balance = 10_000_000_000_000_000_000_000;
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct StorageAccount {
+    pub deposit: Balance,
+    /// Bytes of the player data stored on the contract
+    pub used_bytes: StorageUsage,
+}
+
+impl StorageAccount {
+    fn get_locked_balance(&self) -> Balance {
+        std::cmp::max(MIN_STORAGE_BALANCE, Balance::from(self.used_bytes) * env::storage_byte_cost())
+    }
+
+    fn to_storage_balance(&self) -> StorageBalance {
+        StorageBalance {
+            total: U128(self.deposit),
+            available: U128(self.deposit.saturating_sub(self.get_locked_balance())),
+        }
+    }
+}
+
+impl DeFight {
+    pub(crate) fn assert_storage_registered(&self, account_id: &AccountId) {
+        assert!(self.storage_accounts.get(account_id).is_some(), "Account {} is not registered", account_id);
+    }
+
+    /// Charges the account for the storage used since `initial_storage_usage`, the freed storage is released
+    pub(crate) fn charge_storage(&mut self, account_id: &AccountId, initial_storage_usage: StorageUsage) {
+        let mut storage_account = self.storage_accounts.get(account_id)
+            .unwrap_or_else(|| env::panic(format!("Account {} is not registered", account_id).as_bytes()));
+
+        let storage_usage = env::storage_usage();
+        if storage_usage >= initial_storage_usage {
+            storage_account.used_bytes += storage_usage - initial_storage_usage;
+        } else {
+            storage_account.used_bytes = storage_account.used_bytes.saturating_sub(initial_storage_usage - storage_usage);
+        }
+
+        assert!(
+            storage_account.deposit >= storage_account.get_locked_balance(),
+            "Not enough storage deposit, {} yoctoNEAR is required",
+            storage_account.get_locked_balance()
+        );
+
+        self.storage_accounts.insert(account_id, &storage_account);
+    }
+}
+
+#[near_bindgen]
+impl StorageManagement for DeFight {
+    #[payable]
+    fn storage_deposit(&mut self, account_id: Option<ValidAccountId>, registration_only: Option<bool>) -> StorageBalance {
+        let account_id: AccountId = account_id.map_or_else(env::predecessor_account_id, |account_id| account_id.into());
+        let mut deposit = env::attached_deposit();
+
+        let storage_account = match self.storage_accounts.get(&account_id) {
+            Some(storage_account) if registration_only == Some(true) => {
+                if deposit > 0 {
+                    Promise::new(env::predecessor_account_id()).transfer(deposit);
+                }
+                return storage_account.to_storage_balance();
+            }
+            Some(mut storage_account) => {
+                storage_account.deposit += deposit;
+                storage_account
+            }
+            None => {
+                assert!(deposit >= MIN_STORAGE_BALANCE, "The attached deposit is less than the minimum storage balance");
+                if registration_only == Some(true) {
+                    if deposit > MIN_STORAGE_BALANCE {
+                        Promise::new(env::predecessor_account_id()).transfer(deposit - MIN_STORAGE_BALANCE);
+                    }
+                    deposit = MIN_STORAGE_BALANCE;
+                }
+                StorageAccount { deposit, used_bytes: 0 }
+            }
+        };
+
+        self.storage_accounts.insert(&account_id, &storage_account);
+        storage_account.to_storage_balance()
+    }
+
+    #[payable]
+    fn storage_withdraw(&mut self, amount: Option<U128>) -> StorageBalance {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        let mut storage_account = self.storage_accounts.get(&account_id)
+            .unwrap_or_else(|| env::panic(format!("Account {} is not registered", account_id).as_bytes()));
+
+        let available = storage_account.to_storage_balance().available.0;
+        let amount = amount.map_or(available, |amount| amount.0);
+        assert!(amount <= available, "The amount is greater than the available storage balance");
+
+        storage_account.deposit -= amount;
+        self.storage_accounts.insert(&account_id, &storage_account);
+
+        if amount > 0 {
+            Promise::new(account_id).transfer(amount);
+        }
+
+        storage_account.to_storage_balance()
+    }
+
+    /// The player data is never removed, so only accounts without it can be unregistered
+    #[payable]
+    fn storage_unregister(&mut self, force: Option<bool>) -> bool {
+        assert_one_yocto();
+        assert!(force != Some(true), "Force unregistration is not supported");
+        let account_id = env::predecessor_account_id();
+
+        match self.storage_accounts.get(&account_id) {
+            Some(storage_account) => {
+                assert_eq!(storage_account.used_bytes, 0, "Can't unregister the account with stored player data");
+                self.storage_accounts.remove(&account_id);
+                Promise::new(account_id).transfer(storage_account.deposit);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn storage_balance_bounds(&self) -> StorageBalanceBounds {
+        StorageBalanceBounds { min: U128(MIN_STORAGE_BALANCE), max: None }
+    }
+
+    fn storage_balance_of(&self, account_id: ValidAccountId) -> Option<StorageBalance> {
+        self.storage_accounts.get(account_id.as_ref()).map(|storage_account| storage_account.to_storage_balance())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::{testing_env, MockedBlockchain};
+
+    #[test]
+    fn test_storage_deposit_and_withdraw() {
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(0)).build());
+        let mut contract = DeFight::new();
+
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(1)).attached_deposit(3 * MIN_STORAGE_BALANCE).build());
+        contract.storage_deposit(None, None);
+
+        contract.change_warrior_equipment(EquipmentConfig::default());
+        let used_bytes = contract.storage_accounts.get(&accounts(1).into()).unwrap().used_bytes;
+        assert!(used_bytes > 0);
+
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(1)).attached_deposit(1).build());
+        let storage_balance = contract.storage_withdraw(None);
+        assert_eq!(storage_balance.total.0, MIN_STORAGE_BALANCE);
+        assert_eq!(storage_balance.available.0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "is not registered")]
+    fn test_change_equipment_without_storage() {
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(0)).build());
+        let mut contract = DeFight::new();
+
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(1)).build());
+        contract.change_warrior_equipment(EquipmentConfig::default());
+    }
+}
@@ -0,0 +1,65 @@
+use crate::*;
+use near_sdk::test_utils::{accounts, VMContextBuilder};
+use near_sdk::json_types::ValidAccountId;
+use near_sdk::{testing_env, MockedBlockchain};
+
+pub(crate) fn get_context(predecessor_account_id: ValidAccountId) -> VMContextBuilder {
+    let mut builder = VMContextBuilder::new();
+    builder
+        .current_account_id(accounts(0))
+        .signer_account_id(predecessor_account_id.clone())
+        .predecessor_account_id(predecessor_account_id);
+    builder
+}
+
+pub(crate) fn token_metadata() -> TokenMetadata {
+    TokenMetadata {
+        title: None,
+        description: None,
+        media: None,
+        media_hash: None,
+        copies: None,
+        issued_at: None,
+        expires_at: None,
+        starts_at: None,
+        updated_at: None,
+        extra: None,
+        reference: None,
+        reference_hash: None,
+    }
+}
+
+pub(crate) fn token_series(token_series_id: &str, extra: &str) -> TokenSeries {
+    TokenSeries {
+        contract_id: PARAS_CONTRACT_ID.to_string(),
+        token_series_id: token_series_id.to_string(),
+        metadata: TokenMetadata { extra: Some(extra.to_string()), ..token_metadata() },
+        attributes: ItemAttributes::parse(extra).unwrap(),
+    }
+}
+
+pub(crate) fn paras_token(token_id: &str) -> EquippedToken {
+    EquippedToken { contract_id: PARAS_CONTRACT_ID.to_string(), token_id: token_id.to_string() }
+}
+
+/// Contract owned by `accounts(0)` with four Paras token series and storage of `accounts(1)` and `accounts(2)`
+pub(crate) fn setup_contract() -> DeFight {
+    testing_env!(get_context(accounts(0)).build());
+    let mut contract = DeFight::new();
+    for token_series in [
+        token_series("1", r#"{"place": "helmet", "strength": 2}"#),
+        token_series("2", r#"{"place": "weapon", "strength": 3}"#),
+        token_series("3", r#"{"place": "weapon", "weapon_category": "two_handed"}"#),
+        token_series("4", r#"{"place": "weapon", "weapon_category": "shield"}"#),
+    ] {
+        let key = (token_series.contract_id.clone(), token_series.token_series_id.clone());
+        contract.tokens_series.insert(&key, &ETokenSeries::Current(token_series));
+    }
+    register_storage(&mut contract, &accounts(1).into());
+    register_storage(&mut contract, &accounts(2).into());
+    contract
+}
+
+pub(crate) fn register_storage(contract: &mut DeFight, account_id: &AccountId) {
+    contract.storage_accounts.insert(account_id, &StorageAccount { deposit: 10u128.pow(24), used_bytes: 0 });
+}
@@ -0,0 +1,138 @@
+use crate::*;
+use near_sdk::json_types::{Base58CryptoHash, Base64VecU8};
+use near_sdk::CryptoHash;
+
+/// The staged upgrade is kept outside of the contract struct, so it doesn't depend on the state layout
+const STAGED_CODE_KEY: &[u8] = b"STAGED_CODE";
+const STAGED_UPGRADE_KEY: &[u8] = b"STAGED_UPGRADE";
+
+/// Players have a day to see the staged upgrade before it can be deployed
+const UPGRADE_TIMELOCK: Timestamp = 86_400_000_000_000;
+const GAS_FOR_MIGRATE: Gas = 100_000_000_000_000;
+
+#[derive(BorshDeserialize, BorshSerialize)]
+struct StagedUpgrade {
+    code_hash: CryptoHash,
+    staged_at: Timestamp,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StagedUpgradeView {
+    pub code_hash: Base58CryptoHash,
+    pub staged_at: Timestamp,
+    pub deployable_at: Timestamp,
+}
+
+impl From<StagedUpgrade> for StagedUpgradeView {
+    fn from(staged_upgrade: StagedUpgrade) -> Self {
+        StagedUpgradeView {
+            code_hash: staged_upgrade.code_hash.into(),
+            staged_at: staged_upgrade.staged_at,
+            deployable_at: staged_upgrade.staged_at + UPGRADE_TIMELOCK,
+        }
+    }
+}
+
+fn read_staged_upgrade() -> Option<StagedUpgrade> {
+    env::storage_read(STAGED_UPGRADE_KEY)
+        .map(|value| StagedUpgrade::try_from_slice(&value).expect("Wrong staged upgrade"))
+}
+
+#[near_bindgen]
+impl DeFight {
+    /// Stages the new contract code, it can be deployed after the timelock. Replaces the previously staged code.
+    pub fn stage_upgrade(&mut self, code: Base64VecU8) -> StagedUpgradeView {
+        self.assert_owner();
+        let code: Vec<u8> = code.into();
+        assert!(!code.is_empty(), "Code is empty");
+
+        let mut code_hash = CryptoHash::default();
+        code_hash.copy_from_slice(&env::sha256(&code));
+        let staged_upgrade = StagedUpgrade { code_hash, staged_at: env::block_timestamp() };
+
+        env::storage_write(STAGED_CODE_KEY, &code);
+        env::storage_write(STAGED_UPGRADE_KEY, &staged_upgrade.try_to_vec().unwrap());
+
+        let staged_upgrade_view = StagedUpgradeView::from(staged_upgrade);
+        emit_event("stage_upgrade", &staged_upgrade_view);
+
+        staged_upgrade_view
+    }
+
+    pub fn cancel_upgrade(&mut self) {
+        self.assert_owner();
+        let staged_upgrade = read_staged_upgrade().expect("No staged upgrade");
+
+        env::storage_remove(STAGED_CODE_KEY);
+        env::storage_remove(STAGED_UPGRADE_KEY);
+
+        emit_event("cancel_upgrade", StagedUpgradeView::from(staged_upgrade));
+    }
+
+    /// Deploys the staged code after the timelock and migrates the state with the new code
+    pub fn deploy_upgrade(&mut self) -> Promise {
+        self.assert_owner();
+        let staged_upgrade = read_staged_upgrade().expect("No staged upgrade");
+        assert!(
+            env::block_timestamp() >= staged_upgrade.staged_at + UPGRADE_TIMELOCK,
+            "Upgrade can't be deployed before {}",
+            staged_upgrade.staged_at + UPGRADE_TIMELOCK
+        );
+
+        let code = env::storage_read(STAGED_CODE_KEY).expect("No staged code");
+        env::storage_remove(STAGED_CODE_KEY);
+        env::storage_remove(STAGED_UPGRADE_KEY);
+
+        emit_event("deploy_upgrade", StagedUpgradeView::from(staged_upgrade));
+
+        Promise::new(env::current_account_id())
+            .deploy_contract(code)
+            .function_call(b"migrate".to_vec(), vec![], 0, GAS_FOR_MIGRATE)
+    }
+
+    pub fn get_staged_upgrade(&self) -> Option<StagedUpgradeView> {
+        read_staged_upgrade().map(StagedUpgradeView::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::{testing_env, MockedBlockchain};
+
+    fn setup_contract() -> DeFight {
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(0)).build());
+        let mut contract = DeFight::new();
+        contract.stage_upgrade(b"code".to_vec().into());
+        contract
+    }
+
+    #[test]
+    fn test_stage_and_deploy_upgrade() {
+        let mut contract = setup_contract();
+        let staged_upgrade = contract.get_staged_upgrade().unwrap();
+        assert_eq!(CryptoHash::from(staged_upgrade.code_hash).to_vec(), env::sha256(b"code"));
+        assert_eq!(staged_upgrade.deployable_at, UPGRADE_TIMELOCK);
+
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(0)).block_timestamp(UPGRADE_TIMELOCK).build());
+        contract.deploy_upgrade();
+        assert!(contract.get_staged_upgrade().is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "Upgrade can't be deployed before")]
+    fn test_deploy_upgrade_before_timelock() {
+        let mut contract = setup_contract();
+        contract.deploy_upgrade();
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NO_ACCESS")]
+    fn test_stage_upgrade_without_access() {
+        let mut contract = setup_contract();
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(1)).build());
+        contract.stage_upgrade(b"code".to_vec().into());
+    }
+}
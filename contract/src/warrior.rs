@@ -1,5 +1,10 @@
 use crate::*;
 
+pub(crate) const MAX_ATTACK_SLOTS: u8 = 3;
+const MAX_PROTECT_SLOTS: u8 = 3;
+const AGILITY_PER_ATTACK_SLOT: u16 = 20;
+const INTUITION_PER_PROTECT_SLOT: u16 = 20;
+
 #[derive(BorshDeserialize, BorshSerialize, Clone, Serialize, Deserialize, Debug)]
 #[serde(crate = "near_sdk::serde")]
 pub struct Warrior {
@@ -11,4 +16,93 @@ pub struct Warrior {
     pub intuition: u16,
     pub health: u16,
     pub defense: u16,
+    /// Attacks per round given by the equipment, the stats are added in `get_attack_slots`
+    pub attack_slots: u8,
+    /// Protected parts per round given by the equipment, the stats are added in `get_protect_slots`
+    pub protect_slots: u8,
+    /// Armor of every body part, indexed by `Part::index`
+    pub armor: [u16; 5],
+    /// Every warrior has the power strike, other abilities are given by the equipment
+    pub abilities: Vec<Ability>,
+    /// Status effects put on the opponent by the equipment on a hit
+    pub hit_effects: Vec<StatusEffect>,
+    /// Damage types of the equipped weapons, unarmed warriors do blunt damage
+    pub damage_types: Vec<DamageType>,
+    pub resistances: Vec<Resistance>,
+    /// Extra damage of the attacks in percent
+    pub damage_bonus: u16,
+}
+
+impl Warrior {
+    pub fn new(id: u32, account_id: Option<AccountId>) -> Warrior {
+        Warrior {
+            id,
+            account_id,
+            strength: BASE_STRENGTH,
+            stamina: BASE_STAMINA,
+            agility: BASE_AGILITY,
+            intuition: BASE_INTUITION,
+            health: BASE_HEALTH,
+            defense: BASE_DEFENSE,
+            attack_slots: 1,
+            protect_slots: 1,
+            armor: [0; 5],
+            abilities: vec![Ability::PowerStrike],
+            hit_effects: vec![],
+            damage_types: vec![],
+            resistances: vec![],
+            damage_bonus: 0,
+        }
+    }
+
+    pub fn get_attack_slots(&self) -> usize {
+        let slots = self.attack_slots.saturating_add((self.agility / AGILITY_PER_ATTACK_SLOT) as u8);
+        std::cmp::min(slots, MAX_ATTACK_SLOTS) as usize
+    }
+
+    pub fn get_protect_slots(&self) -> usize {
+        let slots = self.protect_slots.saturating_add((self.intuition / INTUITION_PER_PROTECT_SLOT) as u8);
+        std::cmp::min(slots, MAX_PROTECT_SLOTS) as usize
+    }
+
+    pub(crate) fn add_stats(&mut self, strength: u16, stamina: u16, agility: u16, intuition: u16) {
+        self.strength += strength;
+        self.stamina += stamina;
+        self.agility += agility;
+        self.intuition += intuition;
+        self.health += stamina * 10;
+        self.defense += strength / 2 + agility / 2 + intuition / 2;
+    }
+
+    pub(crate) fn add_ability(&mut self, ability: Ability) {
+        if !self.abilities.contains(&ability) {
+            self.abilities.push(ability);
+        }
+    }
+
+    pub(crate) fn apply_item(&mut self, attributes: &ItemAttributes) {
+        self.add_stats(attributes.strength, attributes.stamina, attributes.agility, attributes.intuition);
+        self.attack_slots += attributes.attack_slots;
+        self.protect_slots += attributes.protect_slots;
+
+        for part in attributes.place.covered_parts() {
+            self.armor[part.index()] += attributes.armor;
+        }
+
+        for ability in &attributes.abilities {
+            self.add_ability(*ability);
+        }
+
+        if let Some(hit_effect) = &attributes.hit_effect {
+            self.hit_effects.push(hit_effect.clone());
+        }
+
+        if let Some(damage_type) = attributes.damage_type {
+            self.damage_types.push(damage_type);
+        }
+
+        for resistance in &attributes.resistances {
+            self.add_resistance(resistance);
+        }
+    }
 }
@@ -0,0 +1,138 @@
+use crate::*;
+
+/// Extra damage of two-handed weapons in percent
+const TWO_HANDED_DAMAGE_BONUS: u16 = 50;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(crate = "near_sdk::serde", rename_all = "snake_case")]
+pub enum WeaponCategory {
+    /// Fits both weapon slots, the second one-handed weapon gives an extra attack
+    OneHanded,
+    /// Equipped as `weapon_1` and occupies both slots, does more damage
+    TwoHanded,
+    /// Equipped only as `weapon_2`, gives an extra protected part
+    Shield,
+}
+
+impl ItemAttributes {
+    /// Weapons without the category are one-handed
+    pub fn get_weapon_category(&self) -> Option<WeaponCategory> {
+        if Place::Weapon1.accepts(self.place) {
+            Some(self.weapon_category.unwrap_or(WeaponCategory::OneHanded))
+        } else {
+            None
+        }
+    }
+}
+
+/// Checks that the weapons in the slots can be combined
+pub(crate) fn check_weapons(weapon_1: Option<WeaponCategory>, weapon_2: Option<WeaponCategory>) -> Result<(), String> {
+    if weapon_1 == Some(WeaponCategory::Shield) {
+        return Err("Shield can be equipped only as Weapon2".to_string());
+    }
+    if weapon_2 == Some(WeaponCategory::TwoHanded) {
+        return Err("Two-handed weapon can be equipped only as Weapon1".to_string());
+    }
+    if weapon_1 == Some(WeaponCategory::TwoHanded) && weapon_2.is_some() {
+        return Err("Two-handed weapon occupies both weapon slots".to_string());
+    }
+    Ok(())
+}
+
+impl Warrior {
+    pub(crate) fn apply_weapon_category(&mut self, place: Place, category: WeaponCategory) {
+        match (place, category) {
+            (Place::Weapon2, WeaponCategory::OneHanded) => self.attack_slots += 1,
+            (_, WeaponCategory::TwoHanded) => self.damage_bonus += TWO_HANDED_DAMAGE_BONUS,
+            (_, WeaponCategory::Shield) => self.protect_slots += 1,
+            _ => {},
+        }
+    }
+}
+
+impl DeFight {
+    fn get_weapon_category(&self, equipment: &EquipmentConfig, place: Place) -> Option<WeaponCategory> {
+        equipment.slot(place).as_ref()
+            .and_then(|token| self.get_item(token))
+            .and_then(|attributes| attributes.get_weapon_category())
+    }
+
+    pub(crate) fn assert_weapons_compatible(&self, equipment: &EquipmentConfig) {
+        let weapon_1 = self.get_weapon_category(equipment, Place::Weapon1);
+        let weapon_2 = self.get_weapon_category(equipment, Place::Weapon2);
+
+        if let Err(e) = check_weapons(weapon_1, weapon_2) {
+            env::panic(e.as_bytes());
+        }
+    }
+
+    /// Weapons that break the rules give nothing. Equipment saved before the rules or before
+    /// the token series was changed can break them.
+    pub(crate) fn get_ignored_weapon_places(&self, equipment: &EquipmentConfig) -> Vec<Place> {
+        let mut weapon_1 = self.get_weapon_category(equipment, Place::Weapon1);
+        let weapon_2 = self.get_weapon_category(equipment, Place::Weapon2);
+        let mut ignored_places = vec![];
+
+        if check_weapons(weapon_1, None).is_err() {
+            ignored_places.push(Place::Weapon1);
+            weapon_1 = None;
+        }
+        if check_weapons(weapon_1, weapon_2).is_err() {
+            ignored_places.push(Place::Weapon2);
+        }
+
+        ignored_places
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{paras_token, setup_contract};
+    use near_sdk::test_utils::accounts;
+
+    #[test]
+    fn test_check_weapons() {
+        assert_eq!(check_weapons(Some(WeaponCategory::OneHanded), Some(WeaponCategory::OneHanded)), Ok(()));
+        assert_eq!(check_weapons(Some(WeaponCategory::OneHanded), Some(WeaponCategory::Shield)), Ok(()));
+        assert_eq!(check_weapons(Some(WeaponCategory::TwoHanded), None), Ok(()));
+        assert!(check_weapons(Some(WeaponCategory::TwoHanded), Some(WeaponCategory::Shield)).is_err());
+        assert!(check_weapons(Some(WeaponCategory::Shield), None).is_err());
+        assert!(check_weapons(None, Some(WeaponCategory::TwoHanded)).is_err());
+    }
+
+    #[test]
+    fn test_apply_weapon_category() {
+        let mut warrior = Warrior::new(1, None);
+        warrior.apply_weapon_category(Place::Weapon1, WeaponCategory::OneHanded);
+        warrior.apply_weapon_category(Place::Weapon2, WeaponCategory::OneHanded);
+        assert_eq!((warrior.attack_slots, warrior.protect_slots, warrior.damage_bonus), (2, 1, 0));
+
+        let mut warrior = Warrior::new(1, None);
+        warrior.apply_weapon_category(Place::Weapon1, WeaponCategory::TwoHanded);
+        assert_eq!(warrior.damage_bonus, TWO_HANDED_DAMAGE_BONUS);
+
+        let mut warrior = Warrior::new(1, None);
+        warrior.apply_weapon_category(Place::Weapon2, WeaponCategory::Shield);
+        assert_eq!(warrior.protect_slots, 2);
+    }
+
+    #[test]
+    fn test_build_warrior_ignores_wrong_weapons() {
+        let contract = setup_contract();
+        let account_id: AccountId = accounts(1).into();
+
+        let equipment = EquipmentConfig {
+            weapon_1: Some(paras_token("3:1")),
+            weapon_2: Some(paras_token("2:5")),
+            ..Default::default()
+        };
+        let warrior = contract.build_warrior(&account_id, &equipment);
+        assert_eq!((warrior.strength, warrior.attack_slots), (BASE_STRENGTH, 1));
+        assert_eq!(warrior.damage_bonus, TWO_HANDED_DAMAGE_BONUS);
+
+        let equipment = EquipmentConfig { weapon_1: Some(paras_token("4:1")), ..Default::default() };
+        let warrior = contract.build_warrior(&account_id, &equipment);
+        assert_eq!(warrior.protect_slots, 1);
+    }
+}